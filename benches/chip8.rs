@@ -0,0 +1,171 @@
+// cargo bench
+//
+// The render group covers the phosphor pass on its own. Window render runs the same
+// compose_frame, upload and CRT pass as Chip8Window::render on a headless GPU
+// adapter, and is skipped without one.
+
+use chip_egg::{Chip8, Display, Engine, Instruction, Options, Palette, Persistence, Phosphor};
+#[cfg(feature = "window")]
+use chip_egg::{compose_frame, upload_frame, CrtEffects, CrtRenderer, Key, KeypadOverlay, Keypad, OverlayMode};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+const ALU_LOOP: [u8; 22] = [
+    0x60, 0x01, 0x61, 0x03, 0x80, 0x14, 0x81, 0x02, 0x81, 0x0E, 0x82, 0x03, 0x73, 0x05, 0x82, 0x35,
+    0x84, 0x26, 0xA3, 0x00, 0x12, 0x04,
+];
+
+// One of each kind of opcode, plus something unknown.
+const OPCODES: [u16; 16] = [
+    0x00E0, 0x1234, 0x3A42, 0x6B07, 0x7C01, 0x8AB4, 0x8AB5, 0x8AB6, 0x9AB0, 0xA300, 0xC0FF, 0xD125,
+    0xF029, 0xF133, 0xF265, 0x5AB1,
+];
+
+fn decode(c: &mut Criterion) {
+    c.bench_function("Instruction::decode", |b| {
+        b.iter(|| {
+            for &opcode in &OPCODES {
+                black_box(Instruction::decode(black_box(opcode)));
+            }
+        })
+    });
+    c.bench_function("Chip8::decode alu", |b| {
+        let mut chip8 = Chip8::new(Options::new()).unwrap();
+        b.iter(|| {
+            for &opcode in &[0x8AB4, 0x8AB5, 0x8AB6, 0x7C01, 0x8AB3] {
+                chip8.decode(black_box(opcode)).unwrap();
+            }
+        })
+    });
+}
+
+fn draw(c: &mut Criterion) {
+    let sprite = [0xFF; 15];
+    c.bench_function("Display::draw", |b| {
+        let mut display = Display::new();
+        b.iter(|| black_box(display.draw(black_box(&sprite), black_box(13), black_box(7))))
+    });
+    c.bench_function("Display::draw across the edge", |b| {
+        let mut display = Display::new();
+        b.iter(|| black_box(display.draw(black_box(&sprite), black_box(60), black_box(25))))
+    });
+}
+
+fn engines(c: &mut Criterion) {
+    let mut group = c.benchmark_group("frame of 1000 instructions");
+    for name in ["interpreter", "cached", "jit"] {
+        let Some(engine) = Engine::from_name(name) else {
+            continue;
+        };
+        group.bench_function(name, |b| {
+            let mut options = Options::new();
+            options.set_engine(engine);
+            let mut chip8 = Chip8::new(options).unwrap();
+            chip8.load_rom(ALU_LOOP.to_vec()).unwrap();
+            b.iter(|| chip8.run_cpu_cycle_alternate(1000).unwrap())
+        });
+    }
+    group.finish();
+}
+
+// A display with a bit of everything on it.
+fn busy_display() -> Display {
+    let mut display = Display::new();
+    for i in 0..16u8 {
+        display.draw(&[0xA5, 0x5A, 0xFF, 0x81, 0x3C], i * 4, i * 2);
+    }
+    display
+}
+
+fn render(c: &mut Criterion) {
+    let display = busy_display();
+    let palette = Palette::classic();
+    let mut frame = vec![0; 64 * 32 * 4];
+    let mut group = c.benchmark_group("render");
+    for persistence in [
+        Persistence::Off,
+        Persistence::Blend(4),
+        Persistence::Decay(0.25),
+    ] {
+        // Warmed up so blending has a full history to average.
+        let mut phosphor = Phosphor::new(persistence);
+        for _ in 0..8 {
+            phosphor.update(&display);
+        }
+        group.bench_function(format!("phosphor {}", persistence), |b| {
+            b.iter(|| {
+                phosphor.update(&display);
+                phosphor.render(&palette, &mut frame);
+            })
+        });
+    }
+    group.finish();
+}
+
+// WindowFrontend::render minus presenting: compose the frame, upload it to the
+// texture pixels would own and draw that through the CRT shader.
+#[cfg(feature = "window")]
+fn window_render(c: &mut Criterion) {
+    const SCALE: u32 = 8;
+    let instance = wgpu::Instance::new(wgpu::Backends::all());
+    let Some(adapter) = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default())) else {
+        eprintln!("No GPU adapter, skipping the window render benches.");
+        return;
+    };
+    let descriptor = wgpu::DeviceDescriptor { label: None, features: wgpu::Features::empty(), limits: adapter.limits() };
+    let (device, queue) = pollster::block_on(adapter.request_device(&descriptor, None)).expect("Error opening the GPU.");
+    let display = busy_display();
+    let palette = Palette::classic();
+    let mut keypad = Keypad::new();
+    keypad.set(Key::K5, true);
+    let mut group = c.benchmark_group("window render");
+    for (name, effects, mode, status) in [
+        ("plain", CrtEffects::NONE, OverlayMode::Off, None),
+        ("crt", CrtEffects::CRT, OverlayMode::Off, None),
+        ("crt, keypad and status", CrtEffects::CRT, OverlayMode::Side, Some("|| 11")),
+    ] {
+        let overlay = KeypadOverlay::new(mode, 64, 32);
+        let (width, height) = overlay.frame_size();
+        let size = |scale: u32| wgpu::Extent3d {
+            width: width as u32 * scale,
+            height: height as u32 * scale,
+            depth_or_array_layers: 1,
+        };
+        let texture = |scale: u32, usage: wgpu::TextureUsages| {
+            device.create_texture(&wgpu::TextureDescriptor {
+                label: None,
+                size: size(scale),
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage,
+            })
+        };
+        let source = texture(1, wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST);
+        let target = texture(SCALE, wgpu::TextureUsages::RENDER_ATTACHMENT);
+        let target = target.create_view(&wgpu::TextureViewDescriptor::default());
+        let crt = CrtRenderer::with_device(&device, &source, wgpu::TextureFormat::Rgba8UnormSrgb, width as u32, height as u32, effects)
+            .expect("CRT shader didn't build");
+        let mut phosphor = Phosphor::new(Persistence::Decay(0.25));
+        phosphor.update(&display);
+        let mut screen = vec![0; 64 * 32 * 4];
+        let mut frame = vec![0; width * height * 4];
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                compose_frame(&phosphor, &overlay, &keypad, &palette, status, &mut screen, &mut frame);
+                upload_frame(&queue, &source, &frame, width as u32, height as u32);
+                let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+                crt.render(&mut encoder, &target, (0, 0, width as u32 * SCALE, height as u32 * SCALE));
+                queue.submit(Some(encoder.finish()));
+                device.poll(wgpu::Maintain::Wait);
+            })
+        });
+    }
+    group.finish();
+}
+
+#[cfg(not(feature = "window"))]
+fn window_render(_c: &mut Criterion) {}
+
+criterion_group!(benches, decode, draw, engines, render, window_render);
+criterion_main!(benches);
@@ -0,0 +1,144 @@
+// A board-agnostic driver loop. On real hardware `Lcd` would be the SPI display
+// driver and `Board` would scan a key matrix, drive a buzzer pin and sleep on a
+// timer. Here both are stand-ins so it runs on the host:
+//
+//     cargo run --example embedded --features embedded
+
+use chip_egg::{AudioSink, Chip8, Clock, Display, GraphicsSink, InputSource, Key, KeyEvent, Options, VideoSink};
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::*;
+
+use std::convert::Infallible;
+
+const INSTRUCTIONS_PER_FRAME: u16 = 11;
+
+// Everything a board needs to provide besides a DrawTarget.
+trait Board {
+    // One bit per keypad key, bit 0 for key 0.
+    fn scan_keys(&mut self) -> u16;
+    fn set_buzzer(&mut self, on: bool);
+    // Sleeps until the next 60Hz frame. Returns false to stop.
+    fn wait_frame(&mut self) -> bool;
+}
+
+// Glues a DrawTarget and a Board together into a Host for `Chip8::run`.
+struct Driver<D: DrawTarget, B: Board> {
+    video: GraphicsSink<D>,
+    board: B,
+    keys: u16,
+    // Keys that changed since the last scan and haven't been handed over yet.
+    changed: u16,
+}
+
+impl<D: DrawTarget, B: Board> VideoSink for Driver<D, B> {
+    fn present(&mut self, display: &Display) {
+        self.video.present(display);
+    }
+}
+
+impl<D: DrawTarget, B: Board> AudioSink for Driver<D, B> {
+    fn set_tone(&mut self, playing: bool) {
+        self.board.set_buzzer(playing);
+    }
+}
+
+impl<D: DrawTarget, B: Board> InputSource for Driver<D, B> {
+    fn poll_key(&mut self) -> Option<KeyEvent> {
+        if self.changed == 0 {
+            return None;
+        }
+        let key = Key::from_nibble(self.changed.trailing_zeros() as u8);
+        self.changed &= !(1 << key.index());
+        Some(KeyEvent::new(key, self.keys & (1 << key.index()) != 0))
+    }
+}
+
+impl<D: DrawTarget, B: Board> Clock for Driver<D, B> {
+    fn wait_frame(&mut self) -> bool {
+        if !self.board.wait_frame() {
+            return false;
+        }
+        let keys = self.board.scan_keys();
+        self.changed |= keys ^ self.keys;
+        self.keys = keys;
+        true
+    }
+}
+
+// A 64x32 monochrome "LCD" that remembers what was drawn to it.
+struct TextLcd {
+    pixels: [[bool; 64]; 32],
+}
+
+impl OriginDimensions for TextLcd {
+    fn size(&self) -> Size {
+        Size::new(64, 32)
+    }
+}
+
+impl DrawTarget for TextLcd {
+    type Color = BinaryColor;
+    type Error = Infallible;
+
+    fn draw_iter<I: IntoIterator<Item = Pixel<BinaryColor>>>(&mut self, pixels: I) -> Result<(), Infallible> {
+        for Pixel(point, color) in pixels {
+            if let (Ok(x @ 0..=63), Ok(y @ 0..=31)) = (usize::try_from(point.x), usize::try_from(point.y)) {
+                self.pixels[y][x] = color.is_on();
+            }
+        }
+        Ok(())
+    }
+}
+
+// Runs a fixed number of frames with nothing pressed.
+struct HostBoard {
+    frames: u32,
+}
+
+impl Board for HostBoard {
+    fn scan_keys(&mut self) -> u16 {
+        0
+    }
+
+    fn set_buzzer(&mut self, _on: bool) {}
+
+    fn wait_frame(&mut self) -> bool {
+        if self.frames == 0 {
+            return false;
+        }
+        self.frames -= 1;
+        true
+    }
+}
+
+fn main() {
+    // Draws the hex digits 0 to B across the top of the screen.
+    let rom = vec![
+        0x60, 0x00, // V0 = 0
+        0x61, 0x00, // V1 = 0
+        0x62, 0x01, // V2 = 1
+        0xF0, 0x29, // I = digit V0
+        0xD1, 0x25, // draw it at V1, V2
+        0x71, 0x05, // V1 += 5
+        0x70, 0x01, // V0 += 1
+        0x30, 0x0C, // skip if V0 == 12
+        0x12, 0x06, // loop
+        0x12, 0x12, // done
+    ];
+    let mut chip8 = Chip8::new(Options::new()).expect("The default fonts fit.");
+    chip8.load_rom(rom).expect("The ROM fits.");
+
+    let lcd = TextLcd { pixels: [[false; 64]; 32] };
+    let mut driver = Driver {
+        video: GraphicsSink::new(lcd, BinaryColor::On, BinaryColor::Off),
+        board: HostBoard { frames: 10 },
+        keys: 0,
+        changed: 0,
+    };
+    chip8.run(&mut driver, INSTRUCTIONS_PER_FRAME).expect("The ROM runs.");
+
+    for row in driver.video.target().pixels.iter().take(8) {
+        let line: String = row.iter().map(|&lit| if lit { '#' } else { '.' }).collect();
+        println!("{}", line);
+    }
+}
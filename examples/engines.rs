@@ -0,0 +1,72 @@
+// Compares how fast each engine runs the same program.
+//
+//     cargo run --release --example engines --features jit [rom.ch8] [frames]
+//
+// Without a ROM it runs a tight loop of register arithmetic, which is the best case
+// for the cached and JIT engines. Real games spend more time drawing.
+
+use chip_egg::{Chip8, Engine, Options};
+
+use std::env;
+use std::fs::read;
+use std::time::Instant;
+
+const INSTRUCTIONS_PER_FRAME: u16 = 10_000;
+
+const ALU_LOOP: [u8; 22] = [
+    0x60, 0x01, // V0 = 1
+    0x61, 0x03, // V1 = 3
+    0x80, 0x14, // V0 += V1
+    0x81, 0x02, // V1 &= V0
+    0x81, 0x0E, // V1 <<= 1
+    0x82, 0x03, // V2 ^= V0
+    0x73, 0x05, // V3 += 5
+    0x82, 0x35, // V2 -= V3
+    0x84, 0x26, // V4 >>= 1
+    0xA3, 0x00, // I = 0x300
+    0x12, 0x04, // loop
+];
+
+fn engines() -> Vec<(&'static str, Engine)> {
+    ["interpreter", "cached", "jit"]
+        .into_iter()
+        .filter_map(|name| Some((name, Engine::from_name(name)?)))
+        .collect()
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let rom = match args.next() {
+        Some(path) => read(path).expect("Error reading ROM."),
+        None => ALU_LOOP.to_vec(),
+    };
+    let frames: u32 = args.next().map_or(600, |frames| frames.parse().expect("Frames should be a number."));
+
+    println!("{:<12} {:>10} {:>14} {:>10}", "engine", "seconds", "instructions/s", "fps");
+    for (name, engine) in engines() {
+        let mut options = Options::new();
+        options.set_engine(engine);
+        options.set_seed(Some(1));
+        let mut chip8 = Chip8::new(options).unwrap();
+        chip8.load_rom(rom.clone()).expect("Error loading ROM.");
+
+        let start = Instant::now();
+        let mut ran = 0;
+        for _ in 0..frames {
+            if let Err(e) = chip8.run_cpu_cycle_alternate(INSTRUCTIONS_PER_FRAME) {
+                println!("{:<12} stopped: {}", name, e);
+                break;
+            }
+            ran += 1;
+        }
+        let seconds = start.elapsed().as_secs_f64();
+        // What actually ran, display waits and FX0A can end a frame early.
+        println!(
+            "{:<12} {:>10.3} {:>14.0} {:>10.0}",
+            name,
+            seconds,
+            chip8.instructions() as f64 / seconds,
+            f64::from(ran) / seconds
+        );
+    }
+}
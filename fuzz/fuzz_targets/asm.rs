@@ -0,0 +1,16 @@
+// Disassembles any bytes and assembles them back, which has to give the same
+// bytes, and assembles the same bytes as text. Source that assembles has to
+// come out the same after a trip through the disassembler.
+
+#![no_main]
+
+use chip_egg::{assemble, disassemble};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    assert_eq!(assemble(&disassemble(data, 0x200)).as_deref(), Ok(data));
+    let source = String::from_utf8_lossy(data);
+    if let Ok(rom) = assemble(&source) {
+        assert_eq!(assemble(&disassemble(&rom, 0x200)), Ok(rom));
+    }
+});
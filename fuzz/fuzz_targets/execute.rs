@@ -0,0 +1,33 @@
+// Runs every opcode against any machine state, which run_rom only gets to slowly.
+//
+// The first 18 bytes are V0-VF and I, the rest are opcodes run one after another
+// from wherever the previous one left the program counter.
+
+#![no_main]
+
+use chip_egg::{Chip8, Instruction, Options};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 18 {
+        return;
+    }
+    let (state, opcodes) = data.split_at(18);
+    let mut options = Options::new();
+    options.set_seed(Some(1));
+    let Ok(mut chip8) = Chip8::new(options) else {
+        return;
+    };
+    // Set V0-VF and I through the opcodes themselves, there's no other way in.
+    for (x, &value) in state[..16].iter().enumerate() {
+        chip8.execute(Instruction::Set(x as u8, value)).unwrap();
+    }
+    chip8.execute(Instruction::SetIndex(u16::from_be_bytes([state[16], state[17]]) & 0xFFF)).unwrap();
+    for opcode in opcodes.chunks_exact(2) {
+        let opcode = u16::from_be_bytes([opcode[0], opcode[1]]);
+        let _ = format!("{:?}", Instruction::decode(opcode));
+        if chip8.decode(opcode).is_err() {
+            break;
+        }
+    }
+});
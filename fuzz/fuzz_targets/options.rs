@@ -0,0 +1,61 @@
+// Runs ROMs under any font address, big font address and memory size, which
+// run_rom leaves at the defaults, along with any stack, key policy, quirks and
+// engine. Fonts that don't fit have to come back as an error from Chip8::new,
+// and everything that does fit has to run like any other.
+//
+// The first 6 bytes are the font address, big font address and memory size
+// (big endian, 0 meaning 64K), then one byte of frame count (low 6 bits) and
+// memory policy (top bit), one byte of engine, quirks, key policy and whether
+// to walk a key down the keypad, one byte of stack depth (low 6 bits, 0 for
+// unlimited) and stack policy (top 2), then the ROM.
+
+#![no_main]
+
+use chip_egg::{Chip8, Engine, Key, KeyPolicy, KeyWait, MemoryPolicy, Options, SpriteEdge, StackPolicy};
+use libfuzzer_sys::fuzz_target;
+
+fn configure(options: &mut Options, config: u8, stack: u8) {
+    let engine = ["interpreter", "cached", "jit", "cached"][(config & 0b11) as usize];
+    options.set_engine(Engine::from_name(engine).unwrap_or(Engine::Cached));
+    options.quirks_mut().set_display_wait(config & 0b100 != 0);
+    if config & 0b1000 != 0 {
+        options.quirks_mut().set_sprite_edge(SpriteEdge::Wrap);
+    }
+    if config & 0b1_0000 != 0 {
+        options.quirks_mut().set_key_wait(KeyWait::Press);
+    }
+    options.set_key_policy([KeyPolicy::Mask, KeyPolicy::Ignore, KeyPolicy::Error, KeyPolicy::Mask][(config >> 5 & 0b11) as usize]);
+    let depth = usize::from(stack & 0x3F);
+    options.set_stack_depth((depth != 0).then_some(depth));
+    options.set_stack_policy([StackPolicy::Error, StackPolicy::Wrap, StackPolicy::Ignore, StackPolicy::Error][(stack >> 6) as usize]);
+}
+
+fuzz_target!(|data: &[u8]| {
+    let [font_high, font_low, big_high, big_low, size_high, size_low, frames, config, stack, rom @ ..] = data else {
+        return;
+    };
+    let mut options = Options::new();
+    options.set_seed(Some(1));
+    options.set_font_address(u16::from_be_bytes([*font_high, *font_low]));
+    options.set_big_font_address(u16::from_be_bytes([*big_high, *big_low]));
+    let size = u16::from_be_bytes([*size_high, *size_low]);
+    options.set_memory_size(if size == 0 { 0x10000 } else { size.into() });
+    if frames & 0x80 != 0 {
+        options.set_memory_policy(MemoryPolicy::Error);
+    }
+    configure(&mut options, *config, *stack);
+    let Ok(mut chip8) = Chip8::new(options) else {
+        return;
+    };
+    if chip8.load_rom(rom.to_vec()).is_err() {
+        return;
+    }
+    for frame in 0..u32::from(frames & 0x3F) {
+        if config & 0x80 != 0 {
+            chip8.set_key(Key::from_nibble((frame / 4) as u8), frame % 4 != 3);
+        }
+        if chip8.run_cpu_cycle_alternate(700 / 60).is_err() {
+            break;
+        }
+    }
+});
@@ -0,0 +1,56 @@
+// Loads any bytes as a ROM and runs it. Errors are fine, panics never are.
+//
+// The first byte picks the options, the second how many frames to run (low 6 bits)
+// and the key policy (top 2), the rest is the ROM.
+
+#![no_main]
+
+use chip_egg::{Chip8, Engine, Key, KeyPolicy, KeyWait, Memory, MemoryPolicy, Options, SpriteEdge, StackPolicy};
+use libfuzzer_sys::fuzz_target;
+
+fn options(config: u8) -> Options {
+    let mut options = Options::new();
+    options.set_seed(Some(1));
+    let engine = ["interpreter", "cached", "jit", "cached"][(config & 0b11) as usize];
+    options.set_engine(Engine::from_name(engine).unwrap_or(Engine::Cached));
+    options.quirks_mut().set_display_wait(config & 0b100 != 0);
+    if config & 0b1000 != 0 {
+        options.quirks_mut().set_sprite_edge(SpriteEdge::Wrap);
+    }
+    if config & 0b1_0000 != 0 {
+        options.quirks_mut().set_key_wait(KeyWait::Press);
+    }
+    if config & 0b10_0000 != 0 {
+        options.set_memory_size(Memory::CLASSIC);
+    }
+    if config & 0b100_0000 != 0 {
+        options.set_memory_policy(MemoryPolicy::Error);
+    }
+    // A small stack so overflows actually happen.
+    options.set_stack_depth(Some(4));
+    if config & 0b1000_0000 != 0 {
+        options.set_stack_policy(StackPolicy::Wrap);
+    }
+    options
+}
+
+fuzz_target!(|data: &[u8]| {
+    let [config, frames, rom @ ..] = data else {
+        return;
+    };
+    let mut options = options(*config);
+    options.set_key_policy([KeyPolicy::Mask, KeyPolicy::Ignore, KeyPolicy::Error, KeyPolicy::Mask][(frames >> 6) as usize]);
+    let Ok(mut chip8) = Chip8::new(options) else {
+        return;
+    };
+    if chip8.load_rom(rom.to_vec()).is_err() {
+        return;
+    }
+    for frame in 0..u32::from(frames & 0x3F) {
+        // Walk a key down the keypad, held for a few frames each.
+        chip8.set_key(Key::from_nibble((frame / 4) as u8), frame % 4 != 3);
+        if chip8.run_cpu_cycle_alternate(700 / 60).is_err() {
+            break;
+        }
+    }
+});
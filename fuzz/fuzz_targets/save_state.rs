@@ -0,0 +1,29 @@
+// Loads any bytes as a save state. Bad states have to be turned down with an
+// error and leave the interpreter as it was, good ones have to save back to the
+// same bytes and then run like any other.
+//
+// The interpreter has 4K of memory and the default stack, the same as the seeds.
+
+#![no_main]
+
+use chip_egg::{Chip8, Key, Memory, Options};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let mut options = Options::new();
+    options.set_seed(Some(1));
+    options.set_memory_size(Memory::CLASSIC);
+    let mut chip8 = Chip8::new(options).unwrap();
+    let before = chip8.save_state();
+    if chip8.load_state(data).is_err() {
+        assert_eq!(chip8.save_state(), before);
+        return;
+    }
+    assert_eq!(chip8.save_state(), data);
+    for frame in 0..30 {
+        chip8.set_key(Key::from_nibble((frame / 4) as u8), frame % 4 != 3);
+        if chip8.run_cpu_cycle_alternate(700 / 60).is_err() {
+            break;
+        }
+    }
+});
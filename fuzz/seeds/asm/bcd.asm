@@ -0,0 +1,27 @@
+CLS               ; 200
+LD V5, 0x00       ; 202
+LD V4, 0x01       ; 204
+LD I, 0x22E       ; 206
+ADD I, V5         ; 208
+LD V0, [I]        ; 20A
+LD I, 0x233       ; 20C
+LD B, V0          ; 20E
+LD V2, [I]        ; 210
+LD V3, 0x01       ; 212
+LD F, V0          ; 214
+DRW V3, V4, 5     ; 216
+ADD V3, 0x05      ; 218
+LD F, V1          ; 21A
+DRW V3, V4, 5     ; 21C
+ADD V3, 0x05      ; 21E
+LD F, V2          ; 220
+DRW V3, V4, 5     ; 222
+ADD V4, 0x06      ; 224
+ADD V5, 0x01      ; 226
+SE V5, 0x05       ; 228
+JP 0x206          ; 22A
+JP 0x22C          ; 22C
+DW 0x0007         ; 22E
+CALL 0xA64        ; 230
+DW 0xFF00         ; 232
+DW 0x0000         ; 234
@@ -0,0 +1,250 @@
+CLS               ; 200
+LD VA, 0x00       ; 202
+LD VB, 0x00       ; 204
+LD V0, 0xFF       ; 206
+LD V1, 0x01       ; 208
+ADD V0, V1        ; 20A
+LD VE, VF         ; 20C
+LD I, 0x3EA       ; 20E
+SE V0, 0x00       ; 210
+LD I, 0x3EF       ; 212
+DRW VA, VB, 5     ; 214
+ADD VA, 0x06      ; 216
+LD V0, VE         ; 218
+LD I, 0x3EA       ; 21A
+SE V0, 0x01       ; 21C
+LD I, 0x3EF       ; 21E
+DRW VA, VB, 5     ; 220
+ADD VA, 0x06      ; 222
+LD V0, 0x10       ; 224
+LD V1, 0x20       ; 226
+ADD V0, V1        ; 228
+LD VE, VF         ; 22A
+LD I, 0x3EA       ; 22C
+SE V0, 0x30       ; 22E
+LD I, 0x3EF       ; 230
+DRW VA, VB, 5     ; 232
+ADD VA, 0x06      ; 234
+LD V0, VE         ; 236
+LD I, 0x3EA       ; 238
+SE V0, 0x00       ; 23A
+LD I, 0x3EF       ; 23C
+DRW VA, VB, 5     ; 23E
+ADD VA, 0x06      ; 240
+LD V0, 0x05       ; 242
+LD V1, 0x03       ; 244
+SUB V0, V1        ; 246
+LD VE, VF         ; 248
+LD I, 0x3EA       ; 24A
+SE V0, 0x02       ; 24C
+LD I, 0x3EF       ; 24E
+DRW VA, VB, 5     ; 250
+ADD VA, 0x06      ; 252
+LD V0, VE         ; 254
+LD I, 0x3EA       ; 256
+SE V0, 0x01       ; 258
+LD I, 0x3EF       ; 25A
+DRW VA, VB, 5     ; 25C
+ADD VA, 0x06      ; 25E
+LD V0, 0x03       ; 260
+LD V1, 0x05       ; 262
+SUB V0, V1        ; 264
+LD VE, VF         ; 266
+LD I, 0x3EA       ; 268
+SE V0, 0xFE       ; 26A
+LD I, 0x3EF       ; 26C
+DRW VA, VB, 5     ; 26E
+ADD VA, 0x06      ; 270
+LD V0, VE         ; 272
+LD I, 0x3EA       ; 274
+SE V0, 0x00       ; 276
+LD I, 0x3EF       ; 278
+DRW VA, VB, 5     ; 27A
+ADD VA, 0x06      ; 27C
+LD V0, 0x05       ; 27E
+LD V1, 0x05       ; 280
+SUB V0, V1        ; 282
+LD VE, VF         ; 284
+LD I, 0x3EA       ; 286
+SE V0, 0x00       ; 288
+LD I, 0x3EF       ; 28A
+DRW VA, VB, 5     ; 28C
+ADD VA, 0x06      ; 28E
+LD V0, VE         ; 290
+LD I, 0x3EA       ; 292
+SE V0, 0x01       ; 294
+LD I, 0x3EF       ; 296
+DRW VA, VB, 5     ; 298
+LD VA, 0x00       ; 29A
+ADD VB, 0x06      ; 29C
+LD V0, 0x03       ; 29E
+LD V1, 0x05       ; 2A0
+SUBN V0, V1       ; 2A2
+LD VE, VF         ; 2A4
+LD I, 0x3EA       ; 2A6
+SE V0, 0x02       ; 2A8
+LD I, 0x3EF       ; 2AA
+DRW VA, VB, 5     ; 2AC
+ADD VA, 0x06      ; 2AE
+LD V0, VE         ; 2B0
+LD I, 0x3EA       ; 2B2
+SE V0, 0x01       ; 2B4
+LD I, 0x3EF       ; 2B6
+DRW VA, VB, 5     ; 2B8
+ADD VA, 0x06      ; 2BA
+LD V0, 0x05       ; 2BC
+LD V1, 0x03       ; 2BE
+SUBN V0, V1       ; 2C0
+LD VE, VF         ; 2C2
+LD I, 0x3EA       ; 2C4
+SE V0, 0xFE       ; 2C6
+LD I, 0x3EF       ; 2C8
+DRW VA, VB, 5     ; 2CA
+ADD VA, 0x06      ; 2CC
+LD V0, VE         ; 2CE
+LD I, 0x3EA       ; 2D0
+SE V0, 0x00       ; 2D2
+LD I, 0x3EF       ; 2D4
+DRW VA, VB, 5     ; 2D6
+ADD VA, 0x06      ; 2D8
+LD V0, 0x05       ; 2DA
+SHR V0, V0        ; 2DC
+LD VE, VF         ; 2DE
+LD I, 0x3EA       ; 2E0
+SE V0, 0x02       ; 2E2
+LD I, 0x3EF       ; 2E4
+DRW VA, VB, 5     ; 2E6
+ADD VA, 0x06      ; 2E8
+LD V0, VE         ; 2EA
+LD I, 0x3EA       ; 2EC
+SE V0, 0x01       ; 2EE
+LD I, 0x3EF       ; 2F0
+DRW VA, VB, 5     ; 2F2
+ADD VA, 0x06      ; 2F4
+LD V0, 0x04       ; 2F6
+SHR V0, V0        ; 2F8
+LD VE, VF         ; 2FA
+LD I, 0x3EA       ; 2FC
+SE V0, 0x02       ; 2FE
+LD I, 0x3EF       ; 300
+DRW VA, VB, 5     ; 302
+ADD VA, 0x06      ; 304
+LD V0, VE         ; 306
+LD I, 0x3EA       ; 308
+SE V0, 0x00       ; 30A
+LD I, 0x3EF       ; 30C
+DRW VA, VB, 5     ; 30E
+ADD VA, 0x06      ; 310
+LD V0, 0x81       ; 312
+SHL V0, V0        ; 314
+LD VE, VF         ; 316
+LD I, 0x3EA       ; 318
+SE V0, 0x02       ; 31A
+LD I, 0x3EF       ; 31C
+DRW VA, VB, 5     ; 31E
+ADD VA, 0x06      ; 320
+LD V0, VE         ; 322
+LD I, 0x3EA       ; 324
+SE V0, 0x01       ; 326
+LD I, 0x3EF       ; 328
+DRW VA, VB, 5     ; 32A
+LD VA, 0x00       ; 32C
+ADD VB, 0x06      ; 32E
+LD V0, 0x41       ; 330
+SHL V0, V0        ; 332
+LD VE, VF         ; 334
+LD I, 0x3EA       ; 336
+SE V0, 0x82       ; 338
+LD I, 0x3EF       ; 33A
+DRW VA, VB, 5     ; 33C
+ADD VA, 0x06      ; 33E
+LD V0, VE         ; 340
+LD I, 0x3EA       ; 342
+SE V0, 0x00       ; 344
+LD I, 0x3EF       ; 346
+DRW VA, VB, 5     ; 348
+ADD VA, 0x06      ; 34A
+LD VF, 0xFF       ; 34C
+LD V1, 0x01       ; 34E
+ADD VF, V1        ; 350
+LD V0, VF         ; 352
+LD I, 0x3EA       ; 354
+SE V0, 0x01       ; 356
+LD I, 0x3EF       ; 358
+DRW VA, VB, 5     ; 35A
+ADD VA, 0x06      ; 35C
+LD VF, 0x10       ; 35E
+LD V1, 0x20       ; 360
+ADD VF, V1        ; 362
+LD V0, VF         ; 364
+LD I, 0x3EA       ; 366
+SE V0, 0x00       ; 368
+LD I, 0x3EF       ; 36A
+DRW VA, VB, 5     ; 36C
+ADD VA, 0x06      ; 36E
+LD VF, 0x05       ; 370
+LD V1, 0x03       ; 372
+SUB VF, V1        ; 374
+LD V0, VF         ; 376
+LD I, 0x3EA       ; 378
+SE V0, 0x01       ; 37A
+LD I, 0x3EF       ; 37C
+DRW VA, VB, 5     ; 37E
+ADD VA, 0x06      ; 380
+LD VF, 0x03       ; 382
+LD V1, 0x05       ; 384
+SUB VF, V1        ; 386
+LD V0, VF         ; 388
+LD I, 0x3EA       ; 38A
+SE V0, 0x00       ; 38C
+LD I, 0x3EF       ; 38E
+DRW VA, VB, 5     ; 390
+ADD VA, 0x06      ; 392
+LD VF, 0x05       ; 394
+SHR VF, V0        ; 396
+LD V0, VF         ; 398
+LD I, 0x3EA       ; 39A
+SE V0, 0x01       ; 39C
+LD I, 0x3EF       ; 39E
+DRW VA, VB, 5     ; 3A0
+ADD VA, 0x06      ; 3A2
+LD VF, 0x40       ; 3A4
+SHL VF, V0        ; 3A6
+LD V0, VF         ; 3A8
+LD I, 0x3EA       ; 3AA
+SE V0, 0x00       ; 3AC
+LD I, 0x3EF       ; 3AE
+DRW VA, VB, 5     ; 3B0
+ADD VA, 0x06      ; 3B2
+LD V0, 0x01       ; 3B4
+LD VF, 0xFF       ; 3B6
+ADD V0, VF        ; 3B8
+LD VE, VF         ; 3BA
+LD I, 0x3EA       ; 3BC
+SE V0, 0x00       ; 3BE
+LD I, 0x3EF       ; 3C0
+DRW VA, VB, 5     ; 3C2
+ADD VA, 0x06      ; 3C4
+LD V0, VE         ; 3C6
+LD I, 0x3EA       ; 3C8
+SE V0, 0x01       ; 3CA
+LD I, 0x3EF       ; 3CC
+DRW VA, VB, 5     ; 3CE
+LD VA, 0x00       ; 3D0
+ADD VB, 0x06      ; 3D2
+LD V0, 0x02       ; 3D4
+LD VF, 0x00       ; 3D6
+LD I, 0xFFE       ; 3D8
+ADD I, V0         ; 3DA
+LD V0, VF         ; 3DC
+LD I, 0x3EA       ; 3DE
+SE V0, 0x01       ; 3E0
+LD I, 0x3EF       ; 3E2
+DRW VA, VB, 5     ; 3E4
+ADD VA, 0x06      ; 3E6
+JP 0x3E8          ; 3E8
+JP 0x010          ; 3EA
+LD I, 0x040       ; 3EC
+DW 0x0090         ; 3EE
+LD V0, 0x60       ; 3F0
+SNE V0, V0        ; 3F2
@@ -0,0 +1,15 @@
+CLS               ; 200
+LD V0, 0x00       ; 202
+LD V1, 0x02       ; 204
+LD V2, 0x02       ; 206
+LD F, V0          ; 208
+DRW V1, V2, 5     ; 20A
+ADD V1, 0x06      ; 20C
+ADD V0, 0x01      ; 20E
+SE V0, 0x08       ; 210
+JP 0x218          ; 212
+LD V1, 0x02       ; 214
+ADD V2, 0x07      ; 216
+SE V0, 0x10       ; 218
+JP 0x208          ; 21A
+JP 0x21C          ; 21C
@@ -0,0 +1,58 @@
+CLS               ; 200
+LD VA, 0x00       ; 202
+LD VB, 0x00       ; 204
+LD V0, K          ; 206
+LD I, 0x26A       ; 208
+SE V0, 0x05       ; 20A
+LD I, 0x26F       ; 20C
+DRW VA, VB, 5     ; 20E
+ADD VA, 0x06      ; 210
+LD V1, 0x0A       ; 212
+SKP V1            ; 214
+JP 0x214          ; 216
+LD V0, 0x01       ; 218
+LD I, 0x26A       ; 21A
+SE V0, 0x01       ; 21C
+LD I, 0x26F       ; 21E
+DRW VA, VB, 5     ; 220
+ADD VA, 0x06      ; 222
+LD V1, 0x03       ; 224
+LD V0, 0x01       ; 226
+SKNP V1           ; 228
+LD V0, 0x00       ; 22A
+LD I, 0x26A       ; 22C
+SE V0, 0x01       ; 22E
+LD I, 0x26F       ; 230
+DRW VA, VB, 5     ; 232
+ADD VA, 0x06      ; 234
+LD V1, 0x0A       ; 236
+LD V0, 0x01       ; 238
+SKNP V1           ; 23A
+LD V0, 0x02       ; 23C
+LD I, 0x26A       ; 23E
+SE V0, 0x02       ; 240
+LD I, 0x26F       ; 242
+DRW VA, VB, 5     ; 244
+ADD VA, 0x06      ; 246
+LD V0, 0x01       ; 248
+SKP V1            ; 24A
+LD V0, 0x00       ; 24C
+LD I, 0x26A       ; 24E
+SE V0, 0x01       ; 250
+LD I, 0x26F       ; 252
+DRW VA, VB, 5     ; 254
+ADD VA, 0x06      ; 256
+SKNP V1           ; 258
+JP 0x258          ; 25A
+LD V0, 0x01       ; 25C
+LD I, 0x26A       ; 25E
+SE V0, 0x01       ; 260
+LD I, 0x26F       ; 262
+DRW VA, VB, 5     ; 264
+ADD VA, 0x06      ; 266
+JP 0x268          ; 268
+JP 0x010          ; 26A
+LD I, 0x040       ; 26C
+DW 0x0090         ; 26E
+LD V0, 0x60       ; 270
+SNE V0, V0        ; 272
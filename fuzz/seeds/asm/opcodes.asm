@@ -0,0 +1,232 @@
+CLS               ; 200
+LD VA, 0x00       ; 202
+LD VB, 0x00       ; 204
+LD V0, 0x55       ; 206
+LD I, 0x3AE       ; 208
+SE V0, 0x55       ; 20A
+LD I, 0x3B3       ; 20C
+DRW VA, VB, 5     ; 20E
+ADD VA, 0x06      ; 210
+ADD V0, 0x01      ; 212
+LD I, 0x3AE       ; 214
+SE V0, 0x56       ; 216
+LD I, 0x3B3       ; 218
+DRW VA, VB, 5     ; 21A
+ADD VA, 0x06      ; 21C
+ADD V0, 0xFF      ; 21E
+LD I, 0x3AE       ; 220
+SE V0, 0x55       ; 222
+LD I, 0x3B3       ; 224
+DRW VA, VB, 5     ; 226
+ADD VA, 0x06      ; 228
+LD V1, 0x03       ; 22A
+LD V0, V1         ; 22C
+LD I, 0x3AE       ; 22E
+SE V0, 0x03       ; 230
+LD I, 0x3B3       ; 232
+DRW VA, VB, 5     ; 234
+ADD VA, 0x06      ; 236
+LD V0, 0x0C       ; 238
+LD V1, 0x0A       ; 23A
+OR V0, V1         ; 23C
+LD I, 0x3AE       ; 23E
+SE V0, 0x0E       ; 240
+LD I, 0x3B3       ; 242
+DRW VA, VB, 5     ; 244
+ADD VA, 0x06      ; 246
+LD V0, 0x0C       ; 248
+AND V0, V1        ; 24A
+LD I, 0x3AE       ; 24C
+SE V0, 0x08       ; 24E
+LD I, 0x3B3       ; 250
+DRW VA, VB, 5     ; 252
+ADD VA, 0x06      ; 254
+LD V0, 0x0C       ; 256
+XOR V0, V1        ; 258
+LD I, 0x3AE       ; 25A
+SE V0, 0x06       ; 25C
+LD I, 0x3B3       ; 25E
+DRW VA, VB, 5     ; 260
+ADD VA, 0x06      ; 262
+LD V0, 0x00       ; 264
+SE V0, 0x00       ; 266
+LD V0, 0x01       ; 268
+LD I, 0x3AE       ; 26A
+SE V0, 0x00       ; 26C
+LD I, 0x3B3       ; 26E
+DRW VA, VB, 5     ; 270
+ADD VA, 0x06      ; 272
+SE V0, 0x01       ; 274
+LD V0, 0x02       ; 276
+LD I, 0x3AE       ; 278
+SE V0, 0x02       ; 27A
+LD I, 0x3B3       ; 27C
+DRW VA, VB, 5     ; 27E
+ADD VA, 0x06      ; 280
+SNE V0, 0x02      ; 282
+LD V0, 0x09       ; 284
+LD I, 0x3AE       ; 286
+SE V0, 0x09       ; 288
+LD I, 0x3B3       ; 28A
+DRW VA, VB, 5     ; 28C
+LD VA, 0x00       ; 28E
+ADD VB, 0x06      ; 290
+SNE V0, 0x00      ; 292
+LD V0, 0x03       ; 294
+LD I, 0x3AE       ; 296
+SE V0, 0x09       ; 298
+LD I, 0x3B3       ; 29A
+DRW VA, VB, 5     ; 29C
+ADD VA, 0x06      ; 29E
+LD V0, 0x07       ; 2A0
+LD V1, 0x07       ; 2A2
+SE V0, V1         ; 2A4
+LD V0, 0x00       ; 2A6
+LD I, 0x3AE       ; 2A8
+SE V0, 0x07       ; 2AA
+LD I, 0x3B3       ; 2AC
+DRW VA, VB, 5     ; 2AE
+ADD VA, 0x06      ; 2B0
+LD V1, 0x08       ; 2B2
+SE V0, V1         ; 2B4
+LD V0, 0x00       ; 2B6
+LD I, 0x3AE       ; 2B8
+SE V0, 0x00       ; 2BA
+LD I, 0x3B3       ; 2BC
+DRW VA, VB, 5     ; 2BE
+ADD VA, 0x06      ; 2C0
+LD V0, 0x07       ; 2C2
+SNE V0, V1        ; 2C4
+LD V0, 0x00       ; 2C6
+LD I, 0x3AE       ; 2C8
+SE V0, 0x07       ; 2CA
+LD I, 0x3B3       ; 2CC
+DRW VA, VB, 5     ; 2CE
+ADD VA, 0x06      ; 2D0
+LD V1, 0x08       ; 2D2
+LD V0, 0x08       ; 2D4
+SNE V0, V1        ; 2D6
+LD V0, 0x00       ; 2D8
+LD I, 0x3AE       ; 2DA
+SE V0, 0x00       ; 2DC
+LD I, 0x3B3       ; 2DE
+DRW VA, VB, 5     ; 2E0
+ADD VA, 0x06      ; 2E2
+CALL 0x3B8        ; 2E4
+LD I, 0x3AE       ; 2E6
+SE V0, 0x11       ; 2E8
+LD I, 0x3B3       ; 2EA
+DRW VA, VB, 5     ; 2EC
+ADD VA, 0x06      ; 2EE
+LD V0, 0x02       ; 2F0
+JP V0, 0x3C2      ; 2F2
+LD I, 0x3AE       ; 2F4
+SE V0, 0x33       ; 2F6
+LD I, 0x3B3       ; 2F8
+DRW VA, VB, 5     ; 2FA
+ADD VA, 0x06      ; 2FC
+LD I, 0x3CC       ; 2FE
+LD V0, 0x01       ; 300
+LD V1, 0x02       ; 302
+LD V2, 0x03       ; 304
+LD [I], V2        ; 306
+LD V0, 0x00       ; 308
+LD V1, 0x00       ; 30A
+LD V2, 0x00       ; 30C
+LD I, 0x3CC       ; 30E
+LD V2, [I]        ; 310
+ADD V0, V1        ; 312
+ADD V0, V2        ; 314
+LD I, 0x3AE       ; 316
+SE V0, 0x06       ; 318
+LD I, 0x3B3       ; 31A
+DRW VA, VB, 5     ; 31C
+ADD VA, 0x06      ; 31E
+LD I, 0x3CC       ; 320
+LD V3, 0x02       ; 322
+ADD I, V3         ; 324
+LD V0, [I]        ; 326
+LD I, 0x3AE       ; 328
+SE V0, 0x03       ; 32A
+LD I, 0x3B3       ; 32C
+DRW VA, VB, 5     ; 32E
+ADD VA, 0x06      ; 330
+LD V0, 0xFE       ; 332
+LD I, 0x3CC       ; 334
+LD B, V0          ; 336
+LD V2, [I]        ; 338
+LD I, 0x3AE       ; 33A
+SE V0, 0x02       ; 33C
+LD I, 0x3B3       ; 33E
+DRW VA, VB, 5     ; 340
+LD VA, 0x00       ; 342
+ADD VB, 0x06      ; 344
+LD V0, V1         ; 346
+LD I, 0x3AE       ; 348
+SE V0, 0x05       ; 34A
+LD I, 0x3B3       ; 34C
+DRW VA, VB, 5     ; 34E
+ADD VA, 0x06      ; 350
+LD V0, V2         ; 352
+LD I, 0x3AE       ; 354
+SE V0, 0x04       ; 356
+LD I, 0x3B3       ; 358
+DRW VA, VB, 5     ; 35A
+ADD VA, 0x06      ; 35C
+LD V0, 0x0A       ; 35E
+LD F, V0          ; 360
+LD V1, [I]        ; 362
+LD I, 0x3AE       ; 364
+SE V0, 0xF0       ; 366
+LD I, 0x3B3       ; 368
+DRW VA, VB, 5     ; 36A
+ADD VA, 0x06      ; 36C
+LD V0, V1         ; 36E
+LD I, 0x3AE       ; 370
+SE V0, 0x90       ; 372
+LD I, 0x3B3       ; 374
+DRW VA, VB, 5     ; 376
+ADD VA, 0x06      ; 378
+RND V0, 0x00      ; 37A
+LD I, 0x3AE       ; 37C
+SE V0, 0x00       ; 37E
+LD I, 0x3B3       ; 380
+DRW VA, VB, 5     ; 382
+ADD VA, 0x06      ; 384
+RND V0, 0x0F      ; 386
+LD V1, 0xF0       ; 388
+AND V0, V1        ; 38A
+LD I, 0x3AE       ; 38C
+SE V0, 0x00       ; 38E
+LD I, 0x3B3       ; 390
+DRW VA, VB, 5     ; 392
+ADD VA, 0x06      ; 394
+LD V0, 0x05       ; 396
+LD DT, V0         ; 398
+LD V0, DT         ; 39A
+SNE V0, 0x00      ; 39C
+JP 0x3A2          ; 39E
+JP 0x39A          ; 3A0
+LD I, 0x3AE       ; 3A2
+SE V0, 0x00       ; 3A4
+LD I, 0x3B3       ; 3A6
+DRW VA, VB, 5     ; 3A8
+ADD VA, 0x06      ; 3AA
+JP 0x3AC          ; 3AC
+JP 0x010          ; 3AE
+LD I, 0x040       ; 3B0
+DW 0x0090         ; 3B2
+LD V0, 0x60       ; 3B4
+SNE V0, V0        ; 3B6
+CALL 0x3BE        ; 3B8
+ADD V0, 0x01      ; 3BA
+RET               ; 3BC
+LD V0, 0x10       ; 3BE
+RET               ; 3C0
+JP 0x3C8          ; 3C2
+LD V0, 0x33       ; 3C4
+JP 0x2F4          ; 3C6
+LD V0, 0x11       ; 3C8
+JP 0x2F4          ; 3CA
+DW 0x0000         ; 3CC
+DW 0x0000         ; 3CE
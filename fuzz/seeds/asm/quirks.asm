@@ -0,0 +1,33 @@
+CLS               ; 200
+LD V0, 0x10       ; 202
+LD DT, V0         ; 204
+LD V2, 0x08       ; 206
+LD V3, 0x00       ; 208
+LD I, 0x238       ; 20A
+DRW V3, V3, 1     ; 20C
+ADD V2, 0xFF      ; 20E
+SE V2, 0x00       ; 210
+JP 0x20C          ; 212
+LD V1, DT         ; 214
+LD V0, 0x10       ; 216
+SUB V0, V1        ; 218
+LD I, 0x23E       ; 21A
+LD B, V0          ; 21C
+LD V2, [I]        ; 21E
+LD V3, 0x08       ; 220
+LD V4, 0x08       ; 222
+LD F, V1          ; 224
+DRW V3, V4, 5     ; 226
+ADD V3, 0x05      ; 228
+LD F, V2          ; 22A
+DRW V3, V4, 5     ; 22C
+LD V3, 0x3C       ; 22E
+LD V4, 0x1D       ; 230
+LD I, 0x239       ; 232
+DRW V3, V4, 5     ; 234
+JP 0x236          ; 236
+DW 0x80FF         ; 238
+OR V1, V8         ; 23A
+DW 0x81FF         ; 23C
+DW 0x0000         ; 23E
+DB 0x00           ; 240
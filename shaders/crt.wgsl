@@ -0,0 +1,92 @@
+// CRT effects over the pixels texture, drawn into the scaled area of the surface.
+// Mirrors crt::apply in the library, tests/shaders.rs checks the two agree.
+
+struct Locals {
+    texture_size: vec2<f32>,
+    effects: u32,
+    _padding: u32,
+}
+@group(0) @binding(0) var r_tex_color: texture_2d<f32>;
+@group(0) @binding(1) var r_tex_sampler: sampler;
+@group(0) @binding(2) var<uniform> r_locals: Locals;
+
+let SCANLINES: u32 = 1u;
+let CURVATURE: u32 = 2u;
+let BLOOM: u32 = 4u;
+let PIXEL_GRID: u32 = 8u;
+
+let CURVE: f32 = 0.1;
+let BLOOM_STRENGTH: f32 = 0.35;
+let GRID_WIDTH: f32 = 0.08;
+let GRID_DARKEN: f32 = 0.6;
+let TAU: f32 = 6.283185307;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+// One full-screen triangle, no vertex buffer needed.
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let x = f32(i32(index & 1u) * 4 - 1);
+    let y = f32(i32(index >> 1u) * 4 - 1);
+    var out: VertexOutput;
+    out.position = vec4<f32>(x, y, 0.0, 1.0);
+    out.uv = vec2<f32>((x + 1.0) * 0.5, (1.0 - y) * 0.5);
+    return out;
+}
+
+fn has(effect: u32) -> bool {
+    return (r_locals.effects & effect) != 0u;
+}
+
+fn texel(cell: vec2<f32>) -> vec3<f32> {
+    let size = r_locals.texture_size;
+    let clamped = clamp(cell, vec2<f32>(0.0), size - 1.0);
+    return textureSampleLevel(r_tex_color, r_tex_sampler, (clamped + 0.5) / size, 0.0).rgb;
+}
+
+@fragment
+fn fs_main(@location(0) in_uv: vec2<f32>) -> @location(0) vec4<f32> {
+    let size = r_locals.texture_size;
+    var uv = in_uv;
+
+    if has(CURVATURE) {
+        let c = uv * 2.0 - 1.0;
+        uv = c * (1.0 + CURVE * c.yx * c.yx) * 0.5 + 0.5;
+        if any(uv < vec2<f32>(0.0)) || any(uv >= vec2<f32>(1.0)) {
+            return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+        }
+    }
+
+    let cell = floor(uv * size);
+    var color = texel(cell);
+
+    if has(BLOOM) {
+        var glow = vec3<f32>(0.0);
+        for (var dy = -1; dy <= 1; dy++) {
+            for (var dx = -1; dx <= 1; dx++) {
+                if dx != 0 || dy != 0 {
+                    glow += texel(cell + vec2<f32>(f32(dx), f32(dy))) / 8.0;
+                }
+            }
+        }
+        color += glow * BLOOM_STRENGTH;
+    }
+
+    var brightness = 1.0;
+    if has(SCANLINES) {
+        let row = fract(uv.y * size.y);
+        brightness *= 0.75 - 0.25 * cos(row * TAU);
+    }
+    if has(PIXEL_GRID) {
+        let c = fract(uv * size);
+        let edge = min(min(c.x, 1.0 - c.x), min(c.y, 1.0 - c.y));
+        if edge < GRID_WIDTH {
+            brightness *= GRID_DARKEN;
+        }
+    }
+
+    return vec4<f32>(clamp(color * brightness, vec3<f32>(0.0), vec3<f32>(1.0)), 1.0);
+}
@@ -0,0 +1,67 @@
+// ROMs to assembly and back, one instruction per two bytes. Code and data aren't
+// told apart, so a sprite comes out as whatever instructions its bytes spell,
+// but assembling a disassembly always gives back the same bytes.
+
+use alloc::{format, string::{String, ToString}, vec::Vec};
+
+use crate::instruction::parse_number;
+use crate::{Error, Instruction};
+
+// Each line gets the address it would load at as a comment, from `start` on.
+// An odd byte at the end comes out as `DB`.
+pub fn disassemble(rom: &[u8], start: u16) -> String {
+    let mut text = String::new();
+    for (i, bytes) in rom.chunks(2).enumerate() {
+        let line = match *bytes {
+            [high, low] => Instruction::decode(u16::from_be_bytes([high, low])).to_string(),
+            [byte] => format!("DB {:#04X}", byte),
+            _ => unreachable!(),
+        };
+        let address = usize::from(start) + i * 2;
+        text.push_str(&format!("{:<18}; {:03X}\n", line, address));
+    }
+    text
+}
+
+// Anything `Instruction::parse` takes, plus `DB` for a single byte. Everything
+// after a `;` is a comment.
+pub fn assemble(source: &str) -> Result<Vec<u8>, Error> {
+    let mut rom = Vec::new();
+    for (i, line) in source.lines().enumerate() {
+        let line = line.split(';').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let error = Error::Syntax { line: i + 1 };
+        match line.split_once(char::is_whitespace) {
+            Some((db, byte)) if db.eq_ignore_ascii_case("DB") => {
+                rom.push(parse_number(byte.trim()).and_then(|byte| u8::try_from(byte).ok()).ok_or(error)?)
+            }
+            _ => rom.extend(Instruction::parse(line).ok_or(error)?.encode().to_be_bytes()),
+        }
+    }
+    Ok(rom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disassembles_with_addresses() {
+        assert_eq!(disassemble(&[0x00, 0xE0, 0xA2, 0x2A, 0x12], 0x200), "CLS               ; 200\nLD I, 0x22A       ; 202\nDB 0x12           ; 204\n");
+    }
+
+    #[test]
+    fn assembling_a_disassembly_gives_the_rom_back() {
+        let rom: Vec<u8> = (0..=255).chain((0..=255).rev()).chain([0x7]).collect();
+        assert_eq!(assemble(&disassemble(&rom, 0x200)), Ok(rom));
+    }
+
+    #[test]
+    fn reports_the_line_that_didnt_assemble() {
+        assert_eq!(assemble("cls\n\n  ; just a comment\nld v0, 256\n"), Err(Error::Syntax { line: 4 }));
+        assert_eq!(assemble("db 0x100"), Err(Error::Syntax { line: 1 }));
+        assert_eq!(assemble("LD V1, 5 ; five\ndb #FF"), Ok(alloc::vec![0x61, 0x05, 0xFF]));
+    }
+}
@@ -0,0 +1,64 @@
+// The CHIP-8 buzzer: a square wave that plays while the sound timer is running.
+
+use chip_egg::AudioSink;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{Sample, SampleFormat, Stream, StreamConfig};
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+const TONE: f32 = 440.0;
+const VOLUME: f32 = 0.1;
+
+pub struct Beeper {
+    // Never touched again, but the sound stops once it's dropped.
+    _stream: Stream,
+    playing: Arc<AtomicBool>,
+}
+
+impl Beeper {
+    // Opens the default output device. Fails if there isn't one, the caller carries on silently.
+    pub fn new() -> Result<Beeper, String> {
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or_else(|| "No audio output device.".to_owned())?;
+        let config = device.default_output_config().map_err(|e| e.to_string())?;
+        let playing = Arc::new(AtomicBool::new(false));
+        let stream = match config.sample_format() {
+            SampleFormat::F32 => build_stream::<f32>(&device, &config.into(), playing.clone()),
+            SampleFormat::I16 => build_stream::<i16>(&device, &config.into(), playing.clone()),
+            SampleFormat::U16 => build_stream::<u16>(&device, &config.into(), playing.clone()),
+        }?;
+        stream.play().map_err(|e| e.to_string())?;
+        Ok(Beeper { _stream: stream, playing })
+    }
+}
+
+impl AudioSink for Beeper {
+    fn set_tone(&mut self, playing: bool) {
+        self.playing.store(playing, Ordering::Relaxed);
+    }
+}
+
+fn build_stream<T: Sample>(device: &cpal::Device, config: &StreamConfig, playing: Arc<AtomicBool>) -> Result<Stream, String> {
+    let sample_rate = config.sample_rate.0 as f32;
+    let channels = config.channels as usize;
+    let mut phase = 0.0f32;
+    device
+        .build_output_stream(
+            config,
+            move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+                let on = playing.load(Ordering::Relaxed);
+                for frame in data.chunks_mut(channels) {
+                    let value = if on && phase < 0.5 { VOLUME } else if on { -VOLUME } else { 0.0 };
+                    phase = (phase + TONE / sample_rate) % 1.0;
+                    let sample = T::from(&value);
+                    for out in frame.iter_mut() {
+                        *out = sample;
+                    }
+                }
+            },
+            |e| log::error!("Audio stream error: {}", e),
+        )
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,24 @@
+// Runs the ROM as fast as possible with nothing drawn, to see how quick the interpreter is.
+
+use chip_egg::Chip8;
+
+use crate::TICK_RATE;
+
+use std::time::Instant;
+
+// Runs until at least `instructions` have executed and prints the speed to stderr.
+pub fn run(mut chip8: Chip8, instructions: u64) -> Result<(), String> {
+    let start = Instant::now();
+    let mut frames = 0u64;
+    while chip8.instructions() < instructions {
+        // Every frame runs at least one instruction, even with the display wait quirk.
+        chip8.run_cpu_cycle_alternate(TICK_RATE)?;
+        frames += 1;
+    }
+    let seconds = start.elapsed().as_secs_f64().max(f64::EPSILON);
+    let frames_per_second = frames as f64 / seconds;
+    eprintln!("{} instructions in {} frames, {:.3}s", chip8.instructions(), frames, seconds);
+    eprintln!("{:.0} instructions/s", chip8.instructions() as f64 / seconds);
+    eprintln!("{:.0} frames/s ({:.1}x full speed)", frames_per_second, frames_per_second / 60.0);
+    Ok(())
+}
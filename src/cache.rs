@@ -0,0 +1,328 @@
+// Straight-line runs of instructions decoded once and kept by their start
+// address, so hot loops skip fetching and decoding every time around.
+
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+
+use crate::{Error, Instruction, Memory};
+
+// How the interpreter gets from opcodes in memory to running them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Engine {
+    // Fetch and decode every instruction as it runs.
+    #[default]
+    Interpreter,
+    // Run pre-decoded blocks out of a BlockCache.
+    Cached,
+    // Like Cached, with register-only runs compiled to native code.
+    #[cfg(all(feature = "jit", target_arch = "x86_64"))]
+    Jit,
+}
+
+impl Engine {
+    pub fn from_name(name: &str) -> Option<Engine> {
+        match name {
+            "interpreter" => Some(Engine::Interpreter),
+            "cached" => Some(Engine::Cached),
+            #[cfg(all(feature = "jit", target_arch = "x86_64"))]
+            "jit" => Some(Engine::Jit),
+            _ => None,
+        }
+    }
+}
+
+// Longest block in instructions, so an invalidation never has far to look back.
+const MAX_BLOCK: usize = 64;
+
+// Runs before the JIT engine compiles a block, so code that only runs a few times
+// (setup, title screens) never pays for codegen.
+#[cfg(all(feature = "jit", target_arch = "x86_64"))]
+const HOT_BLOCK: u32 = 16;
+
+pub struct Block {
+    start: u16,
+    instructions: Vec<Instruction>,
+    // How many times the JIT engine has asked for the block, up to HOT_BLOCK.
+    #[cfg(all(feature = "jit", target_arch = "x86_64"))]
+    runs: core::cell::Cell<u32>,
+    // Compiled once the block is hot.
+    #[cfg(all(feature = "jit", target_arch = "x86_64"))]
+    compiled: core::cell::OnceCell<Option<crate::jit::Compiled>>,
+}
+
+impl Block {
+    pub fn start(&self) -> u16 {
+        self.start
+    }
+
+    pub fn instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    // None until the block has been asked for HOT_BLOCK times, the caller interprets it until then.
+    #[cfg(all(feature = "jit", target_arch = "x86_64"))]
+    pub(crate) fn compiled(&self) -> Option<&crate::jit::Compiled> {
+        if self.runs.get() < HOT_BLOCK {
+            self.runs.set(self.runs.get() + 1);
+            return None;
+        }
+        self.compiled.get_or_init(|| crate::jit::compile(&self.instructions)).as_ref()
+    }
+
+    // One past the last byte the block was decoded from.
+    fn end(&self) -> usize {
+        self.start as usize + self.instructions.len() * 2
+    }
+}
+
+#[derive(Default)]
+pub struct BlockCache {
+    blocks: BTreeMap<u16, Rc<Block>>,
+}
+
+impl BlockCache {
+    pub fn new() -> BlockCache {
+        BlockCache::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.blocks.clear();
+    }
+
+    // The block starting at `pc`, decoding it first if needed. Returns None when the
+    // block would run off the end of memory, which is left to the plain interpreter
+    // since wrapping addresses would alias other blocks.
+    pub fn get(&mut self, pc: u16, memory: &Memory) -> Result<Option<Rc<Block>>, Error> {
+        if let Some(block) = self.blocks.get(&pc) {
+            return Ok(Some(block.clone()));
+        }
+        let mut instructions = Vec::new();
+        let mut address = pc as usize;
+        while instructions.len() < MAX_BLOCK {
+            if address + 2 > memory.size() {
+                break;
+            }
+            let opcode = (u16::from(memory.read(address)?) << 8) | u16::from(memory.read(address + 1)?);
+            let instruction = Instruction::decode(opcode);
+            instructions.push(instruction);
+            address += 2;
+            if instruction.ends_block() {
+                break;
+            }
+        }
+        if instructions.is_empty() {
+            return Ok(None);
+        }
+        let block = Rc::new(Block {
+            start: pc,
+            instructions,
+            #[cfg(all(feature = "jit", target_arch = "x86_64"))]
+            runs: core::cell::Cell::new(0),
+            #[cfg(all(feature = "jit", target_arch = "x86_64"))]
+            compiled: core::cell::OnceCell::new(),
+        });
+        self.blocks.insert(pc, block.clone());
+        Ok(Some(block))
+    }
+
+    // Drops every block decoded from memory in `start..end`.
+    pub fn invalidate(&mut self, start: usize, end: usize) {
+        let first = start.saturating_sub(MAX_BLOCK * 2 - 1);
+        if first > u16::MAX as usize {
+            return;
+        }
+        let last = (end.min(u16::MAX as usize + 1)) as u32;
+        let stale: Vec<u16> = self
+            .blocks
+            .range(first as u16..)
+            .take_while(|(&address, _)| (address as u32) < last)
+            .filter(|(_, block)| block.end() > start)
+            .map(|(&address, _)| address)
+            .collect();
+        for address in stale {
+            self.blocks.remove(&address);
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::rng::Rng;
+    use crate::{Chip8, MemoryPolicy, Options};
+    use alloc::vec;
+
+    fn memory_with(program: &[u8]) -> Memory {
+        let mut memory = Memory::new(Memory::CLASSIC, MemoryPolicy::Wrap);
+        memory.load(0x200, program).unwrap();
+        memory
+    }
+
+    #[test]
+    fn blocks_end_on_control_flow() {
+        // V0 = 1, V1 = 2, jump 0x200, V2 = 3.
+        let memory = memory_with(&[0x60, 0x01, 0x61, 0x02, 0x12, 0x00, 0x62, 0x03]);
+        let mut cache = BlockCache::new();
+        let block = cache.get(0x200, &memory).unwrap().unwrap();
+        assert_eq!(
+            block.instructions(),
+            &[Instruction::Set(0, 1), Instruction::Set(1, 2), Instruction::Jump(0x200)]
+        );
+        assert_eq!(cache.len(), 1);
+        assert!(Rc::ptr_eq(&block, &cache.get(0x200, &memory).unwrap().unwrap()));
+    }
+
+    #[test]
+    fn blocks_stop_at_the_end_of_memory() {
+        let memory = Memory::new(Memory::CLASSIC, MemoryPolicy::Wrap);
+        let mut cache = BlockCache::new();
+        assert_eq!(cache.get(0x0FFC, &memory).unwrap().unwrap().instructions().len(), 2);
+        assert!(cache.get(0x0FFF, &memory).unwrap().is_none());
+        assert!(cache.get(0x1000, &memory).unwrap().is_none());
+    }
+
+    #[test]
+    fn writes_invalidate_overlapping_blocks() {
+        let memory = memory_with(&[0x60, 0x01, 0x61, 0x02, 0x12, 0x00, 0x12, 0x06]);
+        let mut cache = BlockCache::new();
+        cache.get(0x200, &memory).unwrap();
+        cache.get(0x206, &memory).unwrap();
+        // Past the end of both blocks.
+        cache.invalidate(0x208, 0x20A);
+        assert_eq!(cache.len(), 2);
+        // The last byte of the first block.
+        cache.invalidate(0x205, 0x206);
+        assert_eq!(cache.len(), 1);
+        cache.invalidate(0x100, 0x300);
+        assert!(cache.is_empty());
+    }
+
+    #[cfg(all(feature = "jit", target_arch = "x86_64"))]
+    #[test]
+    fn only_hot_blocks_are_compiled() {
+        // V0 += 1, V1 = V0, jump back.
+        let memory = memory_with(&[0x70, 0x01, 0x81, 0x00, 0x12, 0x00]);
+        let mut cache = BlockCache::new();
+        let block = cache.get(0x200, &memory).unwrap().unwrap();
+        for _ in 0..HOT_BLOCK {
+            assert!(block.compiled().is_none());
+        }
+        assert!(block.compiled.get().is_none(), "a cold block stays interpreted");
+        assert!(block.compiled().is_some());
+    }
+
+    fn chip8_with(engine: Engine, rom: &[u8]) -> Chip8 {
+        let mut options = Options::new();
+        options.set_engine(engine);
+        options.set_memory_size(Memory::CLASSIC);
+        options.set_seed(Some(1));
+        let mut chip8 = Chip8::new(options).unwrap();
+        chip8.load_rom(rom.to_vec()).unwrap();
+        chip8
+    }
+
+    fn assert_same_state(a: &Chip8, b: &Chip8, context: &str) {
+        assert_eq!(a.pc, b.pc, "{}", context);
+        assert_eq!(a.ir, b.ir, "{}", context);
+        assert_eq!(a.vr, b.vr, "{}", context);
+        assert_eq!(a.delay_timer, b.delay_timer, "{}", context);
+        assert_eq!(a.sound_timer, b.sound_timer, "{}", context);
+        assert_eq!(a.stack.entries(), b.stack.entries(), "{}", context);
+        assert_eq!(a.memory.as_slice(), b.memory.as_slice(), "{}", context);
+        assert!(a.display.lit_pixels().eq(b.display.lit_pixels()), "{}", context);
+    }
+
+    // Runs the same ROM on both engines a frame at a time, comparing everything after each one.
+    fn assert_engines_agree(rom: &[u8], frames: usize, tickrate: u16) -> Chip8 {
+        let mut plain = chip8_with(Engine::Interpreter, rom);
+        let mut cached = chip8_with(Engine::Cached, rom);
+        for frame in 0..frames {
+            let a = plain.run_cpu_cycle_alternate(tickrate);
+            let b = cached.run_cpu_cycle_alternate(tickrate);
+            let context = format!("frame {}", frame);
+            assert_eq!(a, b, "{}", context);
+            assert_same_state(&plain, &cached, &context);
+            if a.is_err() {
+                break;
+            }
+        }
+        cached
+    }
+
+    #[test]
+    fn self_modifying_code_runs_the_new_bytes() {
+        let rom = [
+            0x60, 0x63, // V0 = 0x63
+            0x61, 0x07, // V1 = 0x07
+            0x63, 0x05, // V3 = 5, patched to V3 = 7
+            0x33, 0x07, // skip if V3 == 7
+            0x12, 0x0C, // jump to the patch
+            0x12, 0x0A, // done
+            0xA2, 0x04, // I = 0x204
+            0xF1, 0x55, // store V0-V1 over 0x204
+            0x12, 0x00, // go round again
+        ];
+        let chip8 = assert_engines_agree(&rom, 3, 11);
+        assert_eq!(chip8.vr[3], 7);
+        assert_eq!(chip8.pc, 0x20A);
+    }
+
+    #[test]
+    fn display_wait_stops_mid_block() {
+        // Three draws in a row, then spin.
+        let rom = [0xA0, 0x50, 0xD0, 0x05, 0x70, 0x08, 0xD0, 0x05, 0x70, 0x08, 0xD0, 0x05, 0x12, 0x0C];
+        let mut plain = chip8_with(Engine::Interpreter, &rom);
+        let mut cached = chip8_with(Engine::Cached, &rom);
+        plain.options.quirks_mut().set_display_wait(true);
+        cached.options.quirks_mut().set_display_wait(true);
+        for frame in 0..4 {
+            plain.run_cpu_cycle_alternate(11).unwrap();
+            cached.run_cpu_cycle_alternate(11).unwrap();
+            assert_same_state(&plain, &cached, &format!("frame {}", frame));
+        }
+    }
+
+    // Random programs, compared with the plain interpreter a frame at a time.
+    pub(crate) fn assert_random_programs_agree(engine: Engine) {
+        let mut rng = Rng::new(0xC0FFEE);
+        for program in 0..200 {
+            // Fill all of memory after 0x200 so stray jumps land in random code rather than zeros.
+            let mut rom = vec![0; Memory::CLASSIC - 0x200];
+            for pair in rom.chunks_mut(2) {
+                pair[0] = rng.next_u8();
+                pair[1] = rng.next_u8();
+                // Keep jumps and calls inside the program most of the time.
+                if matches!(pair[0] >> 4, 0x1 | 0x2 | 0xA | 0xB) {
+                    pair[0] = (pair[0] & 0xF0) | (0x2 + (pair[0] & 0x7));
+                }
+            }
+            let mut plain = chip8_with(Engine::Interpreter, &rom);
+            let mut cached = chip8_with(engine, &rom);
+            for frame in 0..30 {
+                let a = plain.run_cpu_cycle_alternate(11);
+                let b = cached.run_cpu_cycle_alternate(11);
+                let context = format!("program {} frame {}", program, frame);
+                assert_eq!(a, b, "{}", context);
+                assert_same_state(&plain, &cached, &context);
+                if a.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn random_programs_match_the_interpreter() {
+        assert_random_programs_agree(Engine::Cached);
+    }
+}
@@ -0,0 +1,119 @@
+// Settings that stick around between runs, saved as "key = value" lines.
+// The window and the persistent command line flags change and save them.
+#![cfg_attr(not(feature = "window"), allow(dead_code))]
+
+use std::collections::BTreeMap;
+use std::fs::{read_to_string, write};
+use std::path::PathBuf;
+#[cfg(feature = "gamepad")]
+use std::path::Path;
+
+use chip_egg::{CrtEffects, OverlayMode, Palette, Persistence};
+#[cfg(feature = "gamepad")]
+use chip_egg::{Keymap, PadButton};
+
+const CONFIG_FILE: &str = "chip-egg.cfg";
+
+pub struct Config {
+    path: PathBuf,
+    values: BTreeMap<String, String>,
+    // --scale only lasts for the run, so it's kept out of the saved values.
+    scale: Option<usize>,
+}
+
+impl Config {
+    // A missing or unreadable config file just means nothing was saved yet.
+    pub fn load() -> Config {
+        let path = PathBuf::from(CONFIG_FILE);
+        let values = read_to_string(&path)
+            .map(|text| {
+                text.lines()
+                    .filter(|line| !line.trim_start().starts_with('#'))
+                    .filter_map(|line| line.split_once('='))
+                    .map(|(key, value)| (key.trim().to_owned(), value.trim().to_owned()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Config { path, values, scale: None }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) {
+        self.values.insert(key.to_owned(), value.to_owned());
+    }
+
+    // Bad values fall back to the defaults instead of refusing to start.
+    pub fn palette(&self) -> Palette {
+        self.get("palette")
+            .and_then(|palette| Palette::parse(palette).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn persistence(&self) -> Persistence {
+        self.get("persistence")
+            .and_then(|persistence| Persistence::parse(persistence).ok())
+            .unwrap_or(Persistence::Off)
+    }
+
+    pub fn crt_effects(&self) -> CrtEffects {
+        self.get("crt")
+            .and_then(|effects| CrtEffects::parse(effects).ok())
+            .unwrap_or_default()
+    }
+
+    // The controller profile for a ROM: the default bindings, then whatever "gamepad"
+    // changes, then "gamepad.<ROM file name without the extension>" on top of that.
+    #[cfg(feature = "gamepad")]
+    pub fn gamepad_keymap(&self, rom: &str) -> Keymap<PadButton> {
+        let mut keymap = PadButton::default_keymap();
+        let name = Path::new(rom).file_stem().map(|stem| stem.to_string_lossy().to_lowercase()).unwrap_or_default();
+        for key in ["gamepad".to_owned(), format!("gamepad.{}", name)] {
+            if let Some(bindings) = self.get(&key) {
+                let mut changed = keymap.clone();
+                match changed.parse_into(bindings, PadButton::from_name) {
+                    Ok(()) => keymap = changed,
+                    Err(e) => log::warn!("Ignoring {} in the config: {}", key, e),
+                }
+            }
+        }
+        keymap
+    }
+
+    pub fn keypad_overlay(&self) -> OverlayMode {
+        self.get("keypad")
+            .and_then(|mode| OverlayMode::parse(mode).ok())
+            .unwrap_or_default()
+    }
+
+    // Frames run per frame while fast forwarding.
+    pub fn fast_forward(&self) -> u16 {
+        self.get("fast_forward")
+            .and_then(|rate| rate.parse().ok())
+            .unwrap_or(4)
+    }
+
+    // How much screenshots are blown up by.
+    pub fn scale(&self) -> usize {
+        self.scale
+            .or_else(|| self.get("scale").and_then(|scale| scale.parse().ok()))
+            .unwrap_or(4)
+    }
+
+    pub fn set_scale(&mut self, scale: usize) {
+        self.scale = Some(scale);
+    }
+
+    pub fn save(&self) {
+        let text: String = self
+            .values
+            .iter()
+            .map(|(key, value)| format!("{} = {}\n", key, value))
+            .collect();
+        if let Err(e) = write(&self.path, text) {
+            log::warn!("Couldn't save config to {}: {}", self.path.display(), e);
+        }
+    }
+}
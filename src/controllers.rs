@@ -0,0 +1,105 @@
+// Real controllers through gilrs, for the window. The left stick turns into the
+// Stick directions so joysticks and pads without a D-pad still work.
+
+use std::collections::{HashMap, VecDeque};
+
+use chip_egg::{GamepadBackend, PadButton, PadEvent, PadId};
+use gilrs::{Axis, Button, EventType, Gilrs};
+
+// How far a stick has to be pushed before it counts as a press.
+const STICK_THRESHOLD: f32 = 0.5;
+
+pub struct GilrsBackend {
+    gilrs: Gilrs,
+    // Which way each stick axis is pushed, so only changes turn into button events.
+    sticks: HashMap<(PadId, Axis), Option<PadButton>>,
+    pending: VecDeque<PadEvent>,
+}
+
+impl GilrsBackend {
+    pub fn new() -> Result<GilrsBackend, String> {
+        let gilrs = Gilrs::new().map_err(|e| e.to_string())?;
+        for (_, gamepad) in gilrs.gamepads() {
+            log::info!("Gamepad: {}", gamepad.name());
+        }
+        Ok(GilrsBackend { gilrs, sticks: HashMap::new(), pending: VecDeque::new() })
+    }
+
+    fn stick(&mut self, pad: PadId, axis: Axis, value: f32) {
+        let (negative, positive) = match axis {
+            Axis::LeftStickX => (PadButton::StickLeft, PadButton::StickRight),
+            Axis::DPadX => (PadButton::Left, PadButton::Right),
+            // Up is positive in gilrs.
+            Axis::LeftStickY => (PadButton::StickDown, PadButton::StickUp),
+            Axis::DPadY => (PadButton::Down, PadButton::Up),
+            _ => return,
+        };
+        let direction = if value >= STICK_THRESHOLD {
+            Some(positive)
+        } else if value <= -STICK_THRESHOLD {
+            Some(negative)
+        } else {
+            None
+        };
+        let previous = self.sticks.insert((pad, axis), direction).flatten();
+        if previous == direction {
+            return;
+        }
+        if let Some(button) = previous {
+            self.pending.push_back(PadEvent::Button(pad, button, false));
+        }
+        if let Some(button) = direction {
+            self.pending.push_back(PadEvent::Button(pad, button, true));
+        }
+    }
+}
+
+impl GamepadBackend for GilrsBackend {
+    fn poll_pad(&mut self) -> Option<PadEvent> {
+        while self.pending.is_empty() {
+            let event = self.gilrs.next_event()?;
+            let pad = usize::from(event.id);
+            match event.event {
+                EventType::Connected => {
+                    log::info!("Gamepad connected: {}", self.gilrs.gamepad(event.id).name());
+                    self.pending.push_back(PadEvent::Connected(pad));
+                }
+                EventType::Disconnected => {
+                    log::info!("Gamepad disconnected: {}", self.gilrs.gamepad(event.id).name());
+                    self.sticks.retain(|(stick_pad, _), _| *stick_pad != pad);
+                    self.pending.push_back(PadEvent::Disconnected(pad));
+                }
+                EventType::ButtonPressed(button, _) | EventType::ButtonReleased(button, _) => {
+                    let pressed = matches!(event.event, EventType::ButtonPressed(..));
+                    if let Some(button) = pad_button(button) {
+                        self.pending.push_back(PadEvent::Button(pad, button, pressed));
+                    }
+                }
+                EventType::AxisChanged(axis, value, _) => self.stick(pad, axis, value),
+                _ => {}
+            }
+        }
+        self.pending.pop_front()
+    }
+}
+
+fn pad_button(button: Button) -> Option<PadButton> {
+    let button = match button {
+        Button::DPadUp => PadButton::Up,
+        Button::DPadDown => PadButton::Down,
+        Button::DPadLeft => PadButton::Left,
+        Button::DPadRight => PadButton::Right,
+        Button::South => PadButton::South,
+        Button::East => PadButton::East,
+        Button::West => PadButton::West,
+        Button::North => PadButton::North,
+        Button::LeftTrigger => PadButton::LeftShoulder,
+        Button::RightTrigger => PadButton::RightShoulder,
+        Button::LeftTrigger2 => PadButton::LeftTrigger,
+        Button::RightTrigger2 => PadButton::RightTrigger,
+        Button::Select => PadButton::Select,
+        Button::Start => PadButton::Start,
+        _ => return None,
+    };
+    Some(button)
+}
@@ -0,0 +1,229 @@
+// CRT-style effects for the scaled up screen. The window applies them on the GPU
+// with the WGSL shader in shaders/crt.wgsl, `apply` is the same maths on the CPU that
+// the window falls back to when the adapter can't build the shader's pipeline.
+// tests/shaders.rs checks the two agree, keep them in sync.
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CrtEffects(u32);
+
+impl CrtEffects {
+    pub const NONE: CrtEffects = CrtEffects(0);
+    pub const SCANLINES: CrtEffects = CrtEffects(1);
+    pub const CURVATURE: CrtEffects = CrtEffects(1 << 1);
+    pub const BLOOM: CrtEffects = CrtEffects(1 << 2);
+    pub const PIXEL_GRID: CrtEffects = CrtEffects(1 << 3);
+    pub const CRT: CrtEffects = CrtEffects(Self::SCANLINES.0 | Self::CURVATURE.0 | Self::BLOOM.0);
+
+    // What the hotkey cycles through.
+    const PRESETS: [CrtEffects; 6] = [
+        Self::NONE,
+        Self::SCANLINES,
+        Self::PIXEL_GRID,
+        Self::BLOOM,
+        Self::CURVATURE,
+        Self::CRT,
+    ];
+
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn contains(&self, other: CrtEffects) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn next(&self) -> CrtEffects {
+        let index = Self::PRESETS.iter().position(|preset| preset == self).map_or(0, |i| i + 1);
+        Self::PRESETS[index % Self::PRESETS.len()]
+    }
+
+    // Comma separated effect names, "crt" for the usual combination or "off".
+    pub fn parse(effects: &str) -> Result<CrtEffects, String> {
+        effects.split(',').try_fold(Self::NONE, |all, effect| {
+            let effect = match effect.trim() {
+                "off" => Self::NONE,
+                "scanlines" => Self::SCANLINES,
+                "curvature" => Self::CURVATURE,
+                "bloom" => Self::BLOOM,
+                "grid" => Self::PIXEL_GRID,
+                "crt" => Self::CRT,
+                effect => return Err(format!("Unknown CRT effect \"{}\".", effect)),
+            };
+            Ok(all | effect)
+        })
+    }
+}
+
+impl std::ops::BitOr for CrtEffects {
+    type Output = CrtEffects;
+
+    fn bitor(self, rhs: CrtEffects) -> CrtEffects {
+        CrtEffects(self.0 | rhs.0)
+    }
+}
+
+impl std::fmt::Display for CrtEffects {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.is_empty() {
+            return write!(f, "off");
+        }
+        let names = [
+            (Self::SCANLINES, "scanlines"),
+            (Self::CURVATURE, "curvature"),
+            (Self::BLOOM, "bloom"),
+            (Self::PIXEL_GRID, "grid"),
+        ];
+        let names: Vec<&str> = names
+            .iter()
+            .filter(|(effect, _)| self.contains(*effect))
+            .map(|(_, name)| *name)
+            .collect();
+        write!(f, "{}", names.join(","))
+    }
+}
+
+const CURVATURE: f32 = 0.1;
+const BLOOM: f32 = 0.35;
+const GRID_WIDTH: f32 = 0.08;
+const GRID_DARKEN: f32 = 0.6;
+
+// Scales an RGBA frame of `width` x `height` up by `scale` and applies the effects.
+pub fn apply(effects: CrtEffects, frame: &[u8], width: usize, height: usize, scale: usize) -> Vec<u8> {
+    let out_width = width * scale;
+    let out_height = height * scale;
+    let mut out = vec![0; out_width * out_height * 4];
+    let texel = |x: i64, y: i64| -> [f32; 3] {
+        let x = x.clamp(0, width as i64 - 1) as usize;
+        let y = y.clamp(0, height as i64 - 1) as usize;
+        let i = (y * width + x) * 4;
+        [frame[i] as f32 / 255.0, frame[i + 1] as f32 / 255.0, frame[i + 2] as f32 / 255.0]
+    };
+
+    for oy in 0..out_height {
+        for ox in 0..out_width {
+            // Sample at the centre of the output pixel, like the fragment shader does.
+            let mut u = (ox as f32 + 0.5) / out_width as f32;
+            let mut v = (oy as f32 + 0.5) / out_height as f32;
+            let i = (oy * out_width + ox) * 4;
+            out[i + 3] = 0xFF;
+
+            if effects.contains(CrtEffects::CURVATURE) {
+                let (cu, cv) = (u * 2.0 - 1.0, v * 2.0 - 1.0);
+                u = (cu * (1.0 + CURVATURE * cv * cv)) * 0.5 + 0.5;
+                v = (cv * (1.0 + CURVATURE * cu * cu)) * 0.5 + 0.5;
+                if !(0.0..1.0).contains(&u) || !(0.0..1.0).contains(&v) {
+                    continue;
+                }
+            }
+
+            let sx = (u * width as f32).floor() as i64;
+            let sy = (v * height as f32).floor() as i64;
+            let mut color = texel(sx, sy);
+
+            if effects.contains(CrtEffects::BLOOM) {
+                let mut glow = [0.0; 3];
+                for (dx, dy) in [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)] {
+                    let neighbour = texel(sx + dx, sy + dy);
+                    for c in 0..3 {
+                        glow[c] += neighbour[c] / 8.0;
+                    }
+                }
+                for c in 0..3 {
+                    color[c] += glow[c] * BLOOM;
+                }
+            }
+
+            let mut brightness = 1.0;
+            if effects.contains(CrtEffects::SCANLINES) {
+                let line = (v * height as f32).fract();
+                brightness *= 0.75 - 0.25 * (line * std::f32::consts::TAU).cos();
+            }
+            if effects.contains(CrtEffects::PIXEL_GRID) {
+                let cell_x = (u * width as f32).fract();
+                let cell_y = (v * height as f32).fract();
+                let edge = cell_x.min(1.0 - cell_x).min(cell_y).min(1.0 - cell_y);
+                if edge < GRID_WIDTH {
+                    brightness *= GRID_DARKEN;
+                }
+            }
+
+            for c in 0..3 {
+                out[i + c] = ((color[c] * brightness).clamp(0.0, 1.0) * 255.0).round() as u8;
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2x1 frame, white then black.
+    const FRAME: [u8; 8] = [0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0xFF];
+
+    fn pixel(out: &[u8], width: usize, x: usize, y: usize) -> [u8; 4] {
+        let i = (y * width + x) * 4;
+        [out[i], out[i + 1], out[i + 2], out[i + 3]]
+    }
+
+    #[test]
+    fn no_effects_is_nearest_scaling() {
+        let out = apply(CrtEffects::NONE, &FRAME, 2, 1, 4);
+        assert_eq!(out.len(), 8 * 4 * 4);
+        assert_eq!(pixel(&out, 8, 3, 3), [0xFF; 4]);
+        assert_eq!(pixel(&out, 8, 4, 0), [0x00, 0x00, 0x00, 0xFF]);
+    }
+
+    #[test]
+    fn scanlines_darken_row_edges() {
+        let out = apply(CrtEffects::SCANLINES, &FRAME, 2, 1, 8);
+        let edge = pixel(&out, 16, 0, 0)[0];
+        let middle = pixel(&out, 16, 0, 4)[0];
+        assert!(edge < middle, "{} !< {}", edge, middle);
+    }
+
+    #[test]
+    fn pixel_grid_darkens_cell_borders() {
+        let out = apply(CrtEffects::PIXEL_GRID, &FRAME, 2, 1, 16);
+        assert!(pixel(&out, 32, 0, 8)[0] < pixel(&out, 32, 8, 8)[0]);
+    }
+
+    #[test]
+    fn curvature_blacks_out_the_corners() {
+        let white = [0xFF; 4 * 4];
+        let out = apply(CrtEffects::CURVATURE, &white, 2, 2, 16);
+        assert_eq!(pixel(&out, 32, 0, 0), [0x00, 0x00, 0x00, 0xFF]);
+        assert_eq!(pixel(&out, 32, 16, 16), [0xFF; 4]);
+    }
+
+    #[test]
+    fn bloom_lights_up_dark_neighbours() {
+        let out = apply(CrtEffects::BLOOM, &FRAME, 2, 1, 2);
+        assert!(pixel(&out, 4, 2, 0)[0] > 0);
+    }
+
+    #[test]
+    fn parses_and_prints_effects() {
+        let effects = CrtEffects::parse("scanlines, grid").unwrap();
+        assert_eq!(effects, CrtEffects::SCANLINES | CrtEffects::PIXEL_GRID);
+        assert_eq!(effects.to_string(), "scanlines,grid");
+        assert_eq!(CrtEffects::parse("crt").unwrap().to_string(), "scanlines,curvature,bloom");
+        assert_eq!(CrtEffects::parse(&CrtEffects::NONE.to_string()).unwrap(), CrtEffects::NONE);
+        assert!(CrtEffects::parse("vhs").is_err());
+    }
+
+    #[test]
+    fn next_cycles_through_presets() {
+        let mut effects = CrtEffects::NONE;
+        for _ in 0..CrtEffects::PRESETS.len() {
+            effects = effects.next();
+        }
+        assert_eq!(effects, CrtEffects::NONE);
+    }
+}
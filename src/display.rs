@@ -1,99 +1,377 @@
-const WIDTH: usize = 64;
-const HEIGHT: usize = 32;
+use alloc::vec;
+use alloc::vec::Vec;
 
+use crate::SpriteEdge;
+
+const WIDTH: u8 = 64;
+const HEIGHT: u8 = 32;
+
+// Every plane is a row-major bitset, one bit per pixel, packed into u64 words
+// with the leftmost pixel of each word in the most significant bit.
 pub struct Display {
-    display: [[u8; HEIGHT]; WIDTH],
-    // Probably don't need these... but might need it later if we want to support other CHIP-8 variants.
+    planes: Vec<Vec<u64>>,
     width: u8,
     height: u8,
-    // for redrawing if the display buffer changes.
-    redraw: bool,
+    words_per_row: usize,
+    // Rows that changed since the last reset, for redrawing only what changed.
+    dirty_rows: Vec<bool>,
+    sprite_edge: SpriteEdge,
 }
 
 impl Display {
     pub fn new() -> Display {
-        Display {
-            display: [[0; HEIGHT]; WIDTH],
-            width: WIDTH as u8,
-            height: HEIGHT as u8,
-            redraw: true,
-        }
+        Display::with_size(WIDTH, HEIGHT, 1)
     }
 
-    pub fn display(&self) -> [[u8; HEIGHT]; WIDTH] {
-        self.display
+    // SUPER-CHIP's high resolution mode is 128x64, XO-CHIP adds a second plane.
+    pub fn with_size(width: u8, height: u8, planes: u8) -> Display {
+        let width = width.max(1);
+        let height = height.max(1);
+        let words_per_row = (width as usize).div_ceil(64);
+        Display {
+            planes: vec![vec![0; words_per_row * height as usize]; planes.max(1) as usize],
+            width,
+            height,
+            words_per_row,
+            dirty_rows: vec![true; height as usize],
+            sprite_edge: SpriteEdge::Clip,
+        }
     }
 
     pub fn width(&self) -> u8 {
         self.width
     }
 
-    pub fn set_width(&mut self, new_width: u8) {
-        self.width = new_width;
-    }
-
     pub fn height(&self) -> u8 {
         self.height
     }
 
+    pub fn planes(&self) -> usize {
+        self.planes.len()
+    }
+
+    // Clears the screen.
+    pub fn resize(&mut self, width: u8, height: u8) {
+        let sprite_edge = self.sprite_edge;
+        *self = Display::with_size(width, height, self.planes.len() as u8);
+        self.sprite_edge = sprite_edge;
+    }
+
+    pub fn set_width(&mut self, new_width: u8) {
+        self.resize(new_width, self.height);
+    }
+
     pub fn set_height(&mut self, new_height: u8) {
-        self.height = new_height;
+        self.resize(self.width, new_height);
+    }
+
+    pub fn sprite_edge(&self) -> SpriteEdge {
+        self.sprite_edge
+    }
+
+    pub fn set_sprite_edge(&mut self, sprite_edge: SpriteEdge) {
+        self.sprite_edge = sprite_edge;
+    }
+
+    // Every word of a plane, for save states.
+    pub(crate) fn plane(&self, plane: usize) -> &[u64] {
+        &self.planes[plane]
+    }
+
+    // Fills a plane from a save state. False if `words` is the wrong length or
+    // lights pixels past the right edge.
+    pub(crate) fn load_plane(&mut self, plane: usize, words: &[u64]) -> bool {
+        let padding = match self.width % 64 {
+            0 => 0,
+            used => u64::MAX >> used,
+        };
+        let mut last_words = words.iter().skip(self.words_per_row - 1).step_by(self.words_per_row);
+        if words.len() != self.planes[plane].len() || last_words.any(|word| word & padding != 0) {
+            return false;
+        }
+        self.planes[plane].copy_from_slice(words);
+        self.dirty_rows.iter_mut().for_each(|dirty| *dirty = true);
+        true
     }
 
+    // The packed words of one row of a plane.
+    pub fn row(&self, plane: usize, y: usize) -> &[u64] {
+        &self.planes[plane][y * self.words_per_row..][..self.words_per_row]
+    }
+
+    // Bit n of the value is set if the pixel is lit in plane n, so 0 is off.
+    pub fn pixel(&self, x: usize, y: usize) -> u8 {
+        let word = y * self.words_per_row + x / 64;
+        let bit = 63 - x % 64;
+        self.planes
+            .iter()
+            .enumerate()
+            .fold(0, |pixel, (plane, words)| pixel | (((words[word] >> bit) & 1) as u8) << plane)
+    }
+
+    // (x, y) of every pixel that is lit in any plane, skipping empty words.
+    pub fn lit_pixels(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let width = self.width as usize;
+        (0..self.height as usize).flat_map(move |y| {
+            (0..self.words_per_row).flat_map(move |w| {
+                let mut word = self
+                    .planes
+                    .iter()
+                    .fold(0, |word, plane| word | plane[y * self.words_per_row + w]);
+                core::iter::from_fn(move || {
+                    if word == 0 {
+                        return None;
+                    }
+                    let offset = word.leading_zeros() as usize;
+                    word &= !(1 << (63 - offset));
+                    Some((w * 64 + offset, y))
+                })
+                .filter(move |&(x, _)| x < width)
+            })
+        })
+    }
+
+    pub fn is_row_dirty(&self, y: usize) -> bool {
+        self.dirty_rows[y]
+    }
+
+    pub fn dirty_rows(&self) -> impl Iterator<Item = usize> + '_ {
+        self.dirty_rows.iter().enumerate().filter(|(_, &dirty)| dirty).map(|(y, _)| y)
+    }
+
+    // True if anything changed since the last reset.
     pub fn redraw(&self) -> bool {
-        self.redraw
+        self.dirty_rows.iter().any(|&dirty| dirty)
     }
 
     pub fn reset_redraw(&mut self) {
-        self.redraw = false;
+        self.dirty_rows.iter_mut().for_each(|dirty| *dirty = false);
     }
 
     pub fn clear_screen(&mut self) {
-        self.display = [[0u8; HEIGHT]; WIDTH];
+        for plane in self.planes.iter_mut() {
+            plane.iter_mut().for_each(|word| *word = 0);
+        }
+        self.dirty_rows.iter_mut().for_each(|dirty| *dirty = true);
     }
 
+    // Draws into the first plane.
     pub fn draw(&mut self, sprite: &[u8], vx: u8, vy: u8) -> u8 {
-        let x = vx & (self.width-1);
-        let y = vy & (self.height-1);
+        let width = self.width as usize;
+        let height = self.height as usize;
+        // The starting coordinate always wraps, only the rest of the sprite is clipped.
+        let x = vx as usize % width;
+        let y = vy as usize % height;
+        let wrap = self.sprite_edge == SpriteEdge::Wrap;
         let mut collision = 0;
 
-        // for (row, sprite_row) in sprite.iter().enumerate() {
-        //     if row + y as usize >= self.height as usize {
-        //         break;
-        //     }
-        //     for (col, sprite_pixel) in sprite_row.iter().enumerate() {
-        //         if (col + x) as usize >= self.width as usize {
-        //             break;
-        //         }
-        //         if sprite_pixel == 1 {
-        //             if collision == 0 && display[x][y] == 1 {
-        //                 collision = 1
-        //             }
-        //             display[x][y] ^= 1
-        //         }
-        //     }
-        // }
-
         for (row, sprite_row) in sprite.iter().enumerate() {
-            if row + y as usize >= self.height as usize {
-                break;
-            }
-            for pixel in 0..7 {
-                if x + pixel as u8 >= self.width {
+            let mut py = y + row;
+            if py >= height {
+                if !wrap {
                     break;
                 }
-                if sprite_row & (0x80 >> pixel) != 0 {
-                    if !self.redraw {
-                        self.redraw = true;
+                py %= height;
+            }
+            for pixel in 0..8 {
+                let mut px = x + pixel;
+                if px >= width {
+                    if !wrap {
+                        break;
                     }
-                    if collision == 0 && self.display[x as usize + pixel][y as usize + row] == 1 {
-                        collision = 1
+                    px %= width;
+                }
+                if sprite_row & (0x80 >> pixel) != 0 {
+                    let word = &mut self.planes[0][py * self.words_per_row + px / 64];
+                    let bit = 1 << (63 - px % 64);
+                    if *word & bit != 0 {
+                        collision = 1;
                     }
-                    self.display[x as usize + pixel][y as usize + row] ^= 1
+                    *word ^= bit;
+                    self.dirty_rows[py] = true;
                 }
             }
         }
 
         collision
     }
-}
\ No newline at end of file
+}
+
+impl Default for Display {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLOCK: [u8; 4] = [0xFF; 4];
+
+    fn display(sprite_edge: SpriteEdge) -> Display {
+        let mut display = Display::new();
+        display.set_sprite_edge(sprite_edge);
+        display
+    }
+
+    fn lit(display: &Display) -> usize {
+        display.lit_pixels().count()
+    }
+
+    #[test]
+    fn draws_all_eight_columns() {
+        let mut display = display(SpriteEdge::Clip);
+        display.draw(&[0xFF], 0, 0);
+        for x in 0..8 {
+            assert_eq!(display.pixel(x, 0), 1, "column {} not drawn", x);
+        }
+        assert_eq!(display.pixel(8, 0), 0);
+        assert_eq!(lit(&display), 8);
+    }
+
+    #[test]
+    fn draws_rightmost_sprite_column() {
+        let mut display = display(SpriteEdge::Clip);
+        display.draw(&[0x01], 10, 5);
+        assert_eq!(display.pixel(17, 5), 1);
+        assert_eq!(lit(&display), 1);
+    }
+
+    #[test]
+    fn start_position_wraps_in_both_modes() {
+        for edge in [SpriteEdge::Clip, SpriteEdge::Wrap] {
+            let mut display = display(edge);
+            display.draw(&[0x80], 64 + 3, 32 + 7);
+            assert_eq!(display.pixel(3, 7), 1);
+        }
+    }
+
+    #[test]
+    fn clip_drops_pixels_past_the_edges() {
+        let mut display = display(SpriteEdge::Clip);
+        display.draw(&BLOCK, 60, 30);
+        // 4 columns x 2 rows survive.
+        assert_eq!(lit(&display), 8);
+        assert_eq!(display.pixel(0, 0), 0);
+        assert_eq!(display.pixel(63, 31), 1);
+    }
+
+    #[test]
+    fn wrap_brings_pixels_back_on_the_other_side() {
+        let mut display = display(SpriteEdge::Wrap);
+        display.draw(&BLOCK, 60, 30);
+        assert_eq!(lit(&display), 32);
+        for (x, y) in [(60, 30), (63, 31), (0, 0), (3, 1), (0, 31), (63, 0)] {
+            assert_eq!(display.pixel(x, y), 1, "({}, {}) not drawn", x, y);
+        }
+    }
+
+    #[test]
+    fn no_collision_on_empty_screen_at_every_position() {
+        for edge in [SpriteEdge::Clip, SpriteEdge::Wrap] {
+            for x in 0..64 {
+                for y in 0..32 {
+                    let mut display = display(edge);
+                    assert_eq!(display.draw(&BLOCK, x, y), 0, "{:?} at ({}, {})", edge, x, y);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn redraw_collides_and_erases_at_every_position() {
+        for edge in [SpriteEdge::Clip, SpriteEdge::Wrap] {
+            for x in 0..64 {
+                for y in 0..32 {
+                    let mut display = display(edge);
+                    display.draw(&BLOCK, x, y);
+                    assert_eq!(display.draw(&BLOCK, x, y), 1, "{:?} at ({}, {})", edge, x, y);
+                    assert_eq!(lit(&display), 0, "{:?} at ({}, {})", edge, x, y);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn collision_only_with_visible_pixels_at_every_position() {
+        // A lit pixel at the origin only collides with a sprite whose pixels reach it,
+        // which for clipping means the sprite must start there.
+        for x in 0..64u8 {
+            for y in 0..32u8 {
+                let mut clip = display(SpriteEdge::Clip);
+                clip.draw(&[0x80], 0, 0);
+                let expected = if x == 0 && y == 0 { 1 } else { 0 };
+                assert_eq!(clip.draw(&BLOCK, x, y), expected, "clip at ({}, {})", x, y);
+
+                let mut wrap = display(SpriteEdge::Wrap);
+                wrap.draw(&[0x80], 0, 0);
+                let reaches_x = x == 0 || x >= 64 - 7;
+                let reaches_y = y == 0 || y >= 32 - 3;
+                let expected = if reaches_x && reaches_y { 1 } else { 0 };
+                assert_eq!(wrap.draw(&BLOCK, x, y), expected, "wrap at ({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn collision_with_pixel_in_the_last_column() {
+        let mut display = display(SpriteEdge::Clip);
+        display.draw(&[0x01], 0, 0);
+        assert_eq!(display.draw(&[0x01], 0, 0), 1);
+    }
+
+    #[test]
+    fn collision_on_the_edge_pixels() {
+        for edge in [SpriteEdge::Clip, SpriteEdge::Wrap] {
+            for (x, y) in [(63, 0), (0, 31), (63, 31)] {
+                let mut display = display(edge);
+                display.draw(&[0x80], x, y);
+                assert_eq!(display.draw(&[0x80], x, y), 1, "{:?} at ({}, {})", edge, x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn lit_pixels_matches_pixel() {
+        let mut display = Display::with_size(128, 64, 1);
+        display.draw(&[0xA5, 0x81], 60, 10);
+        display.draw(&[0xFF], 120, 63);
+        let lit: Vec<_> = display.lit_pixels().collect();
+        let mut expected = Vec::new();
+        for y in 0..64 {
+            for x in 0..128 {
+                if display.pixel(x, y) != 0 {
+                    expected.push((x, y));
+                }
+            }
+        }
+        assert_eq!(lit, expected);
+        assert_eq!(lit.len(), 4 + 2 + 8);
+        // Straddles the two words of the row.
+        assert_eq!(display.row(0, 10), &[0b1010_u64, 0x5 << 60]);
+    }
+
+    #[test]
+    fn tracks_dirty_rows() {
+        let mut display = Display::new();
+        assert!(display.redraw());
+        display.reset_redraw();
+        assert!(!display.redraw());
+        display.draw(&[0x80, 0x00, 0x80], 0, 4);
+        assert_eq!(display.dirty_rows().collect::<Vec<_>>(), vec![4, 6]);
+        display.reset_redraw();
+        display.clear_screen();
+        assert_eq!(display.dirty_rows().count(), 32);
+    }
+
+    #[test]
+    fn pixel_combines_planes() {
+        let mut display = Display::with_size(64, 32, 2);
+        display.planes[1][0] = 1 << 63;
+        assert_eq!(display.pixel(0, 0), 0b10);
+        display.draw(&[0x80], 0, 0);
+        assert_eq!(display.pixel(0, 0), 0b11);
+        assert_eq!(display.lit_pixels().count(), 1);
+    }
+}
@@ -0,0 +1,163 @@
+// Draws the display to anything embedded-graphics can draw to, like an SPI LCD
+// driver. Only the rows that changed are sent, which matters on a slow bus.
+
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::Rectangle;
+
+use crate::{Display, VideoSink};
+
+pub struct GraphicsSink<D: DrawTarget> {
+    target: D,
+    on: D::Color,
+    off: D::Color,
+    scale: u32,
+    origin: Point,
+    // The first frame, or the display changing size, redraws everything.
+    size: Option<(usize, usize)>,
+    // The first error from the target. Drawing stops until it is taken.
+    error: Option<D::Error>,
+}
+
+impl<D: DrawTarget> GraphicsSink<D> {
+    pub fn new(target: D, on: D::Color, off: D::Color) -> GraphicsSink<D> {
+        GraphicsSink {
+            target,
+            on,
+            off,
+            scale: 1,
+            origin: Point::zero(),
+            size: None,
+            error: None,
+        }
+    }
+
+    // Each CHIP-8 pixel becomes a scale x scale square.
+    pub fn with_scale(mut self, scale: u32) -> GraphicsSink<D> {
+        self.scale = scale.max(1);
+        self
+    }
+
+    // Where the top left corner of the display goes on the target.
+    pub fn with_origin(mut self, origin: Point) -> GraphicsSink<D> {
+        self.origin = origin;
+        self
+    }
+
+    pub fn target(&self) -> &D {
+        &self.target
+    }
+
+    pub fn target_mut(&mut self) -> &mut D {
+        &mut self.target
+    }
+
+    pub fn into_inner(self) -> D {
+        self.target
+    }
+
+    pub fn take_error(&mut self) -> Option<D::Error> {
+        self.error.take()
+    }
+
+    fn draw_row(&mut self, display: &Display, y: usize) -> Result<(), D::Error> {
+        let width = display.width() as u32;
+        let scale = self.scale;
+        let area = Rectangle::new(
+            self.origin + Point::new(0, (y as u32 * scale) as i32),
+            Size::new(width * scale, scale),
+        );
+        let (on, off) = (self.on, self.off);
+        let colors = (0..scale).flat_map(move |_| {
+            (0..width * scale).map(move |x| if display.pixel((x / scale) as usize, y) != 0 { on } else { off })
+        });
+        self.target.fill_contiguous(&area, colors)
+    }
+}
+
+impl<D: DrawTarget> VideoSink for GraphicsSink<D> {
+    fn present(&mut self, display: &Display) {
+        if self.error.is_some() {
+            return;
+        }
+        let size = (display.width() as usize, display.height() as usize);
+        let full = self.size != Some(size);
+        self.size = Some(size);
+        for y in 0..size.1 {
+            if !full && !display.is_row_dirty(y) {
+                continue;
+            }
+            if let Err(e) = self.draw_row(display, y) {
+                self.error = Some(e);
+                // Whatever was half drawn gets fixed by a full redraw.
+                self.size = None;
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_graphics::mock_display::MockDisplay;
+    use embedded_graphics::pixelcolor::BinaryColor;
+
+    fn mock() -> MockDisplay<BinaryColor> {
+        let mut mock = MockDisplay::new();
+        // Dirty rows get drawn again every time they change.
+        mock.set_allow_overdraw(true);
+        mock
+    }
+
+    #[test]
+    fn draws_lit_pixels() {
+        let mut display = Display::new();
+        display.draw(&[0b1100_0000, 0b0100_0000], 1, 0);
+        let mut sink = GraphicsSink::new(mock(), BinaryColor::On, BinaryColor::Off);
+        sink.present(&display);
+        let mock = sink.into_inner();
+        assert_eq!(mock.get_pixel(Point::new(0, 0)), Some(BinaryColor::Off));
+        assert_eq!(mock.get_pixel(Point::new(1, 0)), Some(BinaryColor::On));
+        assert_eq!(mock.get_pixel(Point::new(2, 0)), Some(BinaryColor::On));
+        assert_eq!(mock.get_pixel(Point::new(1, 1)), Some(BinaryColor::Off));
+        assert_eq!(mock.get_pixel(Point::new(2, 1)), Some(BinaryColor::On));
+        assert_eq!(mock.get_pixel(Point::new(63, 31)), Some(BinaryColor::Off));
+        assert_eq!(mock.get_pixel(Point::new(0, 32)), None);
+    }
+
+    #[test]
+    fn only_redraws_dirty_rows() {
+        let mut display = Display::new();
+        let mut sink = GraphicsSink::new(mock(), BinaryColor::On, BinaryColor::Off);
+        sink.present(&display);
+        display.reset_redraw();
+
+        *sink.target_mut() = mock();
+        display.draw(&[0x80], 0, 5);
+        sink.present(&display);
+        let mock = sink.into_inner();
+        assert_eq!(mock.get_pixel(Point::new(0, 5)), Some(BinaryColor::On));
+        assert_eq!(mock.get_pixel(Point::new(1, 5)), Some(BinaryColor::Off));
+        assert_eq!(mock.get_pixel(Point::new(0, 4)), None);
+        assert_eq!(mock.get_pixel(Point::new(0, 6)), None);
+    }
+
+    #[test]
+    fn scales_and_offsets() {
+        // 64x32 at scale 2 doesn't fit the 64x64 mock, so use a smaller display.
+        let mut display = Display::with_size(16, 8, 1);
+        display.draw(&[0x80], 0, 0);
+        let mut sink = GraphicsSink::new(mock(), BinaryColor::On, BinaryColor::Off)
+            .with_scale(2)
+            .with_origin(Point::new(4, 4));
+        sink.present(&display);
+        let mock = sink.into_inner();
+        assert_eq!(mock.get_pixel(Point::new(3, 3)), None);
+        for (x, y) in [(4, 4), (5, 4), (4, 5), (5, 5)] {
+            assert_eq!(mock.get_pixel(Point::new(x, y)), Some(BinaryColor::On));
+        }
+        assert_eq!(mock.get_pixel(Point::new(6, 4)), Some(BinaryColor::Off));
+        assert_eq!(mock.get_pixel(Point::new(35, 19)), Some(BinaryColor::Off));
+        assert_eq!(mock.get_pixel(Point::new(36, 20)), None);
+    }
+}
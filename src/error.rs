@@ -0,0 +1,65 @@
+// Everything that can stop the interpreter, or turn down a ROM, its options or
+// its source. Kept small and Copy so the core doesn't need an allocator just to
+// report a bad ROM.
+
+use core::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    // A read or write past the end of memory with the Error memory policy.
+    MemoryOutOfBounds(usize),
+    // Data that doesn't fit in memory at the address it was loaded to.
+    LoadTooLarge { len: usize, address: usize, size: usize },
+    // A font that doesn't end before the program starts at 0x200.
+    FontOverlapsProgram { address: usize, len: usize },
+    // The small and big fonts share some bytes, so one would overwrite the other.
+    FontsOverlap { font: usize, big_font: usize },
+    // A fixed stack depth over `Stack::MAX_DEPTH`.
+    StackTooDeep(usize),
+    // A call with the stack already at this depth, with the Error stack policy
+    // (or at any policy once an unlimited stack reaches `Stack::UNLIMITED_DEPTH`).
+    StackOverflow(usize),
+    // A return with nothing on the stack, with the Error stack policy.
+    StackUnderflow,
+    // EX9E/EXA1 asked for a key past F, with the Error key policy.
+    InvalidKey(u8),
+    // A line of assembly `assemble` couldn't make sense of, counting from 1.
+    Syntax { line: usize },
+    // A save state that's cut short, made up, or from different options.
+    InvalidSaveState,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::MemoryOutOfBounds(address) => write!(f, "Memory access out of bounds at {:#06X}.", address),
+            Error::LoadTooLarge { len, address, size } => {
+                write!(f, "{} bytes don't fit at {:#06X} in {} bytes of memory.", len, address, size)
+            }
+            Error::FontOverlapsProgram { address, len } => {
+                write!(f, "A {} byte font at {:#06X} runs into the program at 0x200.", len, address)
+            }
+            Error::FontsOverlap { font, big_font } => {
+                write!(f, "The font at {:#06X} and the big font at {:#06X} overlap.", font, big_font)
+            }
+            Error::StackTooDeep(depth) => {
+                write!(f, "A stack depth of {} is more than the {} allowed.", depth, crate::Stack::MAX_DEPTH)
+            }
+            Error::StackOverflow(depth) => write!(f, "Stack overflow, depth is {}.", depth),
+            Error::StackUnderflow => write!(f, "Stack underflow."),
+            Error::InvalidKey(key) => write!(f, "There's no key {:#04X} on the keypad.", key),
+            Error::Syntax { line } => write!(f, "Can't assemble line {}.", line),
+            Error::InvalidSaveState => write!(f, "Not a save state, or one for different options."),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+// The frontends report everything as strings, this lets them keep using `?`.
+impl From<Error> for alloc::string::String {
+    fn from(error: Error) -> alloc::string::String {
+        alloc::string::ToString::to_string(&error)
+    }
+}
@@ -0,0 +1,43 @@
+// Putting together the picture the window shows each frame. Kept out of the window
+// so the benches time the same code it runs.
+
+use crate::{draw_status, KeypadOverlay, Keypad, OverlayMode, Palette, Phosphor};
+
+// Draws the phosphor's screen into `frame` (RGBA, `overlay.frame_size()`), with the
+// keypad overlay around or over it and the status text in the corner. `screen`
+// holds the CHIP-8 screen on its own while the overlay is up.
+pub fn compose_frame(
+    phosphor: &Phosphor,
+    overlay: &KeypadOverlay,
+    keypad: &Keypad,
+    palette: &Palette,
+    status: Option<&str>,
+    screen: &mut [u8],
+    frame: &mut [u8],
+) {
+    if overlay.mode() == OverlayMode::Off {
+        phosphor.render(palette, frame);
+    } else {
+        phosphor.render(palette, screen);
+        overlay.render(screen, keypad, palette, frame);
+    }
+    if let Some(status) = status {
+        draw_status(status, palette, frame, overlay.frame_size().0);
+    }
+}
+
+// Copies a composed frame into the texture the CRT shader samples. The window's
+// pixels makes this same copy in `render_with`, this is for drawing without one.
+#[cfg(feature = "window")]
+pub fn upload_frame(queue: &wgpu::Queue, texture: &wgpu::Texture, frame: &[u8], width: u32, height: u32) {
+    queue.write_texture(
+        texture.as_image_copy(),
+        frame,
+        wgpu::ImageDataLayout {
+            offset: 0,
+            bytes_per_row: core::num::NonZeroU32::new(width * 4),
+            rows_per_image: core::num::NonZeroU32::new(height),
+        },
+        wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+    );
+}
@@ -0,0 +1,330 @@
+// Gamepads and joysticks on the hex keypad. The library never talks to a
+// controller itself: a `GamepadBackend` hands over button events (the window
+// uses gilrs, tests use `VirtualGamepad`) and `Gamepads` turns them into key
+// events through a `Keymap`, so it can be polled like any other `InputSource`.
+
+use alloc::collections::{BTreeSet, VecDeque};
+
+use crate::{HeldKeys, InputSource, Key, KeyEvent, Keymap};
+
+// Whatever number the backend gives a controller. Stays the same while it's plugged in.
+pub type PadId = usize;
+
+// The buttons of a standard pad, named by position so it doesn't matter whose
+// labels are printed on them. Backends turn the left stick into the Stick directions,
+// kept apart from the D-pad so letting go of one doesn't let go of the other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PadButton {
+    Up,
+    Down,
+    Left,
+    Right,
+    South,
+    East,
+    West,
+    North,
+    LeftShoulder,
+    RightShoulder,
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    StickUp,
+    StickDown,
+    StickLeft,
+    StickRight,
+}
+
+impl PadButton {
+    pub const ALL: [PadButton; 18] = [
+        PadButton::Up, PadButton::Down, PadButton::Left, PadButton::Right,
+        PadButton::South, PadButton::East, PadButton::West, PadButton::North,
+        PadButton::LeftShoulder, PadButton::RightShoulder, PadButton::LeftTrigger, PadButton::RightTrigger,
+        PadButton::Select, PadButton::Start,
+        PadButton::StickUp, PadButton::StickDown, PadButton::StickLeft, PadButton::StickRight,
+    ];
+
+    // What it's called in controller profiles.
+    pub fn name(self) -> &'static str {
+        match self {
+            PadButton::Up => "up",
+            PadButton::Down => "down",
+            PadButton::Left => "left",
+            PadButton::Right => "right",
+            PadButton::South => "south",
+            PadButton::East => "east",
+            PadButton::West => "west",
+            PadButton::North => "north",
+            PadButton::LeftShoulder => "l1",
+            PadButton::RightShoulder => "r1",
+            PadButton::LeftTrigger => "l2",
+            PadButton::RightTrigger => "r2",
+            PadButton::Select => "select",
+            PadButton::Start => "start",
+            PadButton::StickUp => "stick_up",
+            PadButton::StickDown => "stick_down",
+            PadButton::StickLeft => "stick_left",
+            PadButton::StickRight => "stick_right",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<PadButton> {
+        PadButton::ALL.into_iter().find(|button| button.name() == name)
+    }
+
+    // The profile used unless the config says otherwise. The D-pad and the stick are
+    // on 5/7/8/9 like W/A/S/D on the keyboard, with the face buttons on the keys around them.
+    pub fn default_keymap() -> Keymap<PadButton> {
+        [
+            (PadButton::Up, Key::K5),
+            (PadButton::Down, Key::K8),
+            (PadButton::Left, Key::K7),
+            (PadButton::Right, Key::K9),
+            (PadButton::South, Key::K6),
+            (PadButton::East, Key::K4),
+            (PadButton::West, Key::KA),
+            (PadButton::North, Key::KB),
+            (PadButton::LeftShoulder, Key::K1),
+            (PadButton::RightShoulder, Key::K3),
+            (PadButton::LeftTrigger, Key::KC),
+            (PadButton::RightTrigger, Key::KD),
+            (PadButton::Select, Key::KE),
+            (PadButton::Start, Key::KF),
+            (PadButton::StickUp, Key::K5),
+            (PadButton::StickDown, Key::K8),
+            (PadButton::StickLeft, Key::K7),
+            (PadButton::StickRight, Key::K9),
+        ]
+        .into_iter()
+        .collect()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PadEvent {
+    Connected(PadId),
+    Disconnected(PadId),
+    Button(PadId, PadButton, bool),
+}
+
+// Where controller events come from. Polled until it returns None, like `InputSource`.
+pub trait GamepadBackend {
+    fn poll_pad(&mut self) -> Option<PadEvent>;
+}
+
+// A controller driven by hand, for tests and for frontends that get their pad
+// events from somewhere else.
+#[derive(Clone, Debug, Default)]
+pub struct VirtualGamepad {
+    events: VecDeque<PadEvent>,
+}
+
+impl VirtualGamepad {
+    pub fn new() -> VirtualGamepad {
+        VirtualGamepad::default()
+    }
+
+    pub fn push(&mut self, event: PadEvent) {
+        self.events.push_back(event);
+    }
+
+    pub fn connect(&mut self, pad: PadId) {
+        self.push(PadEvent::Connected(pad));
+    }
+
+    pub fn disconnect(&mut self, pad: PadId) {
+        self.push(PadEvent::Disconnected(pad));
+    }
+
+    pub fn press(&mut self, pad: PadId, button: PadButton) {
+        self.push(PadEvent::Button(pad, button, true));
+    }
+
+    pub fn release(&mut self, pad: PadId, button: PadButton) {
+        self.push(PadEvent::Button(pad, button, false));
+    }
+}
+
+impl GamepadBackend for VirtualGamepad {
+    fn poll_pad(&mut self) -> Option<PadEvent> {
+        self.events.pop_front()
+    }
+}
+
+// Every connected controller, mapped through one profile. A key stays down while
+// any button on any pad holds it, and a pad that goes away lets go of its keys.
+pub struct Gamepads<B> {
+    backend: B,
+    keymap: Keymap<PadButton>,
+    // The key each held button pressed, so a release still matches after the keymap changes.
+    held: HeldKeys<(PadId, PadButton)>,
+    connected: BTreeSet<PadId>,
+}
+
+impl<B: GamepadBackend> Gamepads<B> {
+    pub fn new(backend: B, keymap: Keymap<PadButton>) -> Gamepads<B> {
+        Gamepads {
+            backend,
+            keymap,
+            held: HeldKeys::new(),
+            connected: BTreeSet::new(),
+        }
+    }
+
+    pub fn backend_mut(&mut self) -> &mut B {
+        &mut self.backend
+    }
+
+    pub fn keymap(&self) -> &Keymap<PadButton> {
+        &self.keymap
+    }
+
+    pub fn set_keymap(&mut self, keymap: Keymap<PadButton>) {
+        self.keymap = keymap;
+    }
+
+    pub fn connected(&self) -> impl Iterator<Item = PadId> + '_ {
+        self.connected.iter().copied()
+    }
+
+    fn handle(&mut self, event: PadEvent) {
+        match event {
+            PadEvent::Connected(pad) => {
+                self.connected.insert(pad);
+            }
+            PadEvent::Disconnected(pad) => {
+                self.connected.remove(&pad);
+                self.held.release_all(|(held, _)| *held == pad);
+            }
+            PadEvent::Button(pad, button, true) => {
+                // Pads that were plugged in before the backend started never say so.
+                self.connected.insert(pad);
+                if let Some(key) = self.keymap.get(&button) {
+                    self.held.press((pad, button), key);
+                }
+            }
+            PadEvent::Button(pad, button, false) => {
+                self.held.release(&(pad, button));
+            }
+        }
+    }
+}
+
+impl<B: GamepadBackend> InputSource for Gamepads<B> {
+    fn poll_key(&mut self) -> Option<KeyEvent> {
+        loop {
+            if let Some(event) = self.held.poll_key() {
+                return Some(event);
+            }
+            let event = self.backend.poll_pad()?;
+            self.handle(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Chip8, Options};
+    use alloc::vec::Vec;
+
+    fn gamepads() -> Gamepads<VirtualGamepad> {
+        Gamepads::new(VirtualGamepad::new(), PadButton::default_keymap())
+    }
+
+    fn drain(gamepads: &mut Gamepads<VirtualGamepad>) -> Vec<KeyEvent> {
+        core::iter::from_fn(|| gamepads.poll_key()).collect()
+    }
+
+    #[test]
+    fn buttons_map_to_keys() {
+        let mut pads = gamepads();
+        pads.backend_mut().connect(0);
+        pads.backend_mut().press(0, PadButton::Up);
+        pads.backend_mut().press(0, PadButton::Up);
+        pads.backend_mut().press(0, PadButton::South);
+        pads.backend_mut().release(0, PadButton::Up);
+        assert_eq!(
+            drain(&mut pads),
+            [KeyEvent::new(Key::K5, true), KeyEvent::new(Key::K6, true), KeyEvent::new(Key::K5, false)]
+        );
+        assert_eq!(pads.connected().collect::<Vec<_>>(), [0]);
+    }
+
+    #[test]
+    fn shared_keys_stay_down_until_every_button_lets_go() {
+        let mut keymap = PadButton::default_keymap();
+        keymap.bind(PadButton::Start, Key::K5);
+        let mut pads = Gamepads::new(VirtualGamepad::new(), keymap);
+        pads.backend_mut().press(0, PadButton::Up);
+        pads.backend_mut().press(1, PadButton::Up);
+        pads.backend_mut().press(0, PadButton::Start);
+        pads.backend_mut().release(0, PadButton::Up);
+        pads.backend_mut().release(1, PadButton::Up);
+        assert_eq!(drain(&mut pads), [KeyEvent::new(Key::K5, true)]);
+        pads.backend_mut().release(0, PadButton::Start);
+        assert_eq!(drain(&mut pads), [KeyEvent::new(Key::K5, false)]);
+    }
+
+    #[test]
+    fn unplugging_releases_held_keys() {
+        let mut pads = gamepads();
+        pads.backend_mut().connect(0);
+        pads.backend_mut().connect(1);
+        pads.backend_mut().press(0, PadButton::Left);
+        pads.backend_mut().press(0, PadButton::East);
+        pads.backend_mut().press(1, PadButton::East);
+        drain(&mut pads);
+        pads.backend_mut().disconnect(0);
+        assert_eq!(drain(&mut pads), [KeyEvent::new(Key::K7, false)]);
+        assert_eq!(pads.connected().collect::<Vec<_>>(), [1]);
+        // Plugged back in it starts from nothing held.
+        pads.backend_mut().connect(0);
+        pads.backend_mut().release(0, PadButton::Left);
+        assert_eq!(drain(&mut pads), []);
+    }
+
+    #[test]
+    fn releases_follow_the_key_that_was_pressed() {
+        let mut pads = gamepads();
+        pads.backend_mut().press(0, PadButton::North);
+        drain(&mut pads);
+        let mut profile = PadButton::default_keymap();
+        profile.parse_into("north:2 west:-", PadButton::from_name).unwrap();
+        pads.set_keymap(profile);
+        pads.backend_mut().release(0, PadButton::North);
+        pads.backend_mut().press(0, PadButton::West);
+        assert_eq!(drain(&mut pads), [KeyEvent::new(Key::KB, false)]);
+    }
+
+    #[test]
+    fn the_stick_and_d_pad_are_held_apart() {
+        let mut pads = gamepads();
+        pads.backend_mut().press(0, PadButton::Up);
+        pads.backend_mut().press(0, PadButton::StickUp);
+        pads.backend_mut().release(0, PadButton::StickUp);
+        assert_eq!(drain(&mut pads), [KeyEvent::new(Key::K5, true)]);
+        pads.backend_mut().release(0, PadButton::Up);
+        assert_eq!(drain(&mut pads), [KeyEvent::new(Key::K5, false)]);
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for button in PadButton::ALL {
+            assert_eq!(PadButton::from_name(button.name()), Some(button));
+        }
+        assert_eq!(PadButton::from_name("a"), None);
+    }
+
+    #[test]
+    fn drives_the_keypad() {
+        let mut chip8 = Chip8::new(Options::new()).unwrap();
+        let mut pads = gamepads();
+        pads.backend_mut().press(0, PadButton::Right);
+        while let Some(event) = pads.poll_key() {
+            chip8.set_key(event.key, event.pressed);
+        }
+        assert!(chip8.keypad().is_down(Key::K9));
+    }
+}
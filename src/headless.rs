@@ -0,0 +1,85 @@
+use chip_egg::{save_png, save_screenshot, AudioSink, Chip8, Clock, Display, InputSource, KeyEvent, Recorder, VideoSink};
+
+use crate::config::Config;
+use crate::TICK_RATE;
+
+use std::io::Write;
+use std::path::PathBuf;
+
+// Runs a fixed number of frames as fast as possible, with no input and no sound.
+struct HeadlessHost {
+    frames: u32,
+    recorder: Option<Recorder<Box<dyn Write>>>,
+    // The first recording error, reported once the run stops.
+    error: Option<String>,
+}
+
+impl VideoSink for HeadlessHost {
+    fn present(&mut self, display: &Display) {
+        if let Some(recorder) = &mut self.recorder {
+            if let Err(e) = recorder.record(display) {
+                self.error = Some(e);
+            }
+        }
+    }
+}
+
+impl AudioSink for HeadlessHost {
+    fn set_tone(&mut self, _playing: bool) {}
+}
+
+impl InputSource for HeadlessHost {
+    fn poll_key(&mut self) -> Option<KeyEvent> {
+        None
+    }
+}
+
+impl Clock for HeadlessHost {
+    fn wait_frame(&mut self) -> bool {
+        if self.frames == 0 || self.error.is_some() {
+            return false;
+        }
+        self.frames -= 1;
+        true
+    }
+}
+
+// Runs the ROM for a fixed number of frames with no window, then takes a screenshot
+// and saves the state if asked. When recording, the screenshot is only taken if a
+// file name was given.
+pub fn run(
+    mut chip8: Chip8,
+    config: &Config,
+    frames: u32,
+    screenshot: Option<PathBuf>,
+    recorder: Option<Recorder<Box<dyn Write>>>,
+    save_state: Option<PathBuf>,
+) -> Result<(), String> {
+    let mut host = HeadlessHost { frames, recorder, error: None };
+    chip8.run(&mut host, TICK_RATE)?;
+    if let Some(e) = host.error {
+        return Err(e);
+    }
+    if let Some(path) = save_state {
+        std::fs::write(&path, chip8.save_state()).map_err(|e| format!("Couldn't save state to {}: {}", path.display(), e))?;
+    }
+
+    if let Some(recorder) = host.recorder {
+        recorder.finish()?;
+        if screenshot.is_none() {
+            return Ok(());
+        }
+    }
+
+    let palette = config.palette();
+    let scale = config.scale();
+    let path = match screenshot {
+        Some(path) => {
+            save_png(&path, chip8.display(), &palette, scale)?;
+            path
+        }
+        None => save_screenshot(".", chip8.display(), &palette, scale)?,
+    };
+    eprintln!("Saved screenshot to {}", path.display());
+    Ok(())
+}
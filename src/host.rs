@@ -0,0 +1,184 @@
+// The interface between the interpreter and whatever is showing it. A frontend
+// (a window, a terminal, a headless runner or a test) implements these traits
+// and the core drives them one frame at a time, so the library itself never
+// has to know about winit, pixels or any other windowing crate.
+
+use crate::{Chip8, Display, Error, Key};
+
+// Receives the display once per frame. The display's dirty rows say what changed
+// since the previous frame, they are cleared as soon as `present` returns.
+pub trait VideoSink {
+    fn present(&mut self, display: &Display);
+}
+
+// Told once per frame whether the buzzer should be sounding.
+pub trait AudioSink {
+    fn set_tone(&mut self, playing: bool);
+}
+
+// Hands key events to the core. Polled until it returns None at the start of
+// every frame, so a press and release that land in the same frame still count.
+pub trait InputSource {
+    fn poll_key(&mut self) -> Option<KeyEvent>;
+}
+
+// Paces the frames. Returns false once the frontend wants to stop.
+pub trait Clock {
+    fn wait_frame(&mut self) -> bool;
+}
+
+// Everything a frontend needs for `Chip8::run`. Frontends with their own event
+// loop, like the window, only need the first three and call `run_frame` themselves.
+pub trait Host: VideoSink + AudioSink + InputSource + Clock {}
+
+impl<T: VideoSink + AudioSink + InputSource + Clock> Host for T {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KeyEvent {
+    pub key: Key,
+    pub pressed: bool,
+}
+
+impl KeyEvent {
+    pub fn new(key: Key, pressed: bool) -> KeyEvent {
+        KeyEvent { key, pressed }
+    }
+}
+
+impl Chip8 {
+    // Runs a single 60Hz frame: input, timers and `tickrate` instructions, then the buzzer and display.
+    // The keypad's edges are the ones from this frame until the next one starts.
+    pub fn run_frame<H>(&mut self, host: &mut H, tickrate: u16) -> Result<(), Error>
+    where
+        H: VideoSink + AudioSink + InputSource,
+    {
+        self.keypad.clear_edges();
+        while let Some(event) = host.poll_key() {
+            self.set_key(event.key, event.pressed);
+        }
+        self.run_cpu_cycle_alternate(tickrate)?;
+        host.set_tone(self.sound_timer > 0);
+        host.present(&self.display);
+        self.display.reset_redraw();
+        Ok(())
+    }
+
+    // Runs frames until the host's clock says to stop.
+    pub fn run<H: Host>(&mut self, host: &mut H, tickrate: u16) -> Result<(), Error> {
+        while host.wait_frame() {
+            self.run_frame(host, tickrate)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Options;
+    use std::collections::VecDeque;
+
+    // A scripted frontend: replays queued key events and remembers what it was shown.
+    struct TestHost {
+        frames: usize,
+        keys: VecDeque<KeyEvent>,
+        tones: Vec<bool>,
+        presented: Vec<bool>,
+    }
+
+    impl TestHost {
+        fn new(frames: usize) -> TestHost {
+            TestHost { frames, keys: VecDeque::new(), tones: Vec::new(), presented: Vec::new() }
+        }
+    }
+
+    impl VideoSink for TestHost {
+        fn present(&mut self, display: &Display) {
+            self.presented.push(display.redraw());
+        }
+    }
+
+    impl AudioSink for TestHost {
+        fn set_tone(&mut self, playing: bool) {
+            self.tones.push(playing);
+        }
+    }
+
+    impl InputSource for TestHost {
+        fn poll_key(&mut self) -> Option<KeyEvent> {
+            self.keys.pop_front()
+        }
+    }
+
+    impl Clock for TestHost {
+        fn wait_frame(&mut self) -> bool {
+            if self.frames == 0 {
+                return false;
+            }
+            self.frames -= 1;
+            true
+        }
+    }
+
+    fn chip8_with(rom: &[u8]) -> Chip8 {
+        let mut chip8 = Chip8::new(Options::new()).unwrap();
+        chip8.load_rom(rom.to_vec()).unwrap();
+        chip8
+    }
+
+    #[test]
+    fn runs_until_the_clock_stops() {
+        // 00E0 then jump to self.
+        let mut chip8 = chip8_with(&[0x00, 0xE0, 0x12, 0x02]);
+        let mut host = TestHost::new(3);
+        chip8.run(&mut host, 10).unwrap();
+        assert_eq!(host.presented, vec![true, false, false]);
+        assert!(!chip8.display().redraw());
+    }
+
+    #[test]
+    fn buzzer_follows_the_sound_timer() {
+        // V0 = 2, sound timer = V0, then spin.
+        let mut chip8 = chip8_with(&[0x60, 0x02, 0xF0, 0x18, 0x12, 0x04]);
+        let mut host = TestHost::new(4);
+        chip8.run(&mut host, 10).unwrap();
+        assert_eq!(host.tones, vec![true, true, false, false]);
+    }
+
+    #[test]
+    fn taps_within_a_frame_reach_fx0a() {
+        // Wait for a key into V0, then spin.
+        let mut chip8 = chip8_with(&[0xF0, 0x0A, 0x12, 0x02]);
+        let mut host = TestHost::new(1);
+        chip8.run(&mut host, 10).unwrap();
+        assert_eq!(chip8.pc, 0x200);
+
+        host.frames = 2;
+        host.keys.push_back(KeyEvent::new(Key::K7, true));
+        host.keys.push_back(KeyEvent::new(Key::K7, false));
+        chip8.run(&mut host, 10).unwrap();
+        assert_eq!(chip8.vr[0], 0x7);
+        assert_eq!(chip8.pc, 0x202);
+    }
+
+    #[test]
+    fn keypad_edges_last_one_frame() {
+        let mut chip8 = chip8_with(&[0x12, 0x00]);
+        let mut host = TestHost::new(1);
+        host.keys.push_back(KeyEvent::new(Key::K3, true));
+        host.keys.push_back(KeyEvent::new(Key::K7, true));
+        host.keys.push_back(KeyEvent::new(Key::K7, false));
+        chip8.run(&mut host, 10).unwrap();
+        assert_eq!(
+            chip8.keypad().edges().collect::<Vec<_>>(),
+            vec![KeyEvent::new(Key::K3, true), KeyEvent::new(Key::K7, true), KeyEvent::new(Key::K7, false)]
+        );
+        assert!(chip8.keypad().is_down(Key::K3));
+
+        host.frames = 1;
+        chip8.run(&mut host, 10).unwrap();
+        assert_eq!(chip8.keypad().edges().count(), 0);
+        assert!(chip8.keypad().is_down(Key::K3));
+    }
+}
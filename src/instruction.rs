@@ -0,0 +1,393 @@
+// A decoded opcode. Decoding is done once here so the interpreter and the block
+// cache can share the same `Chip8::execute`. Register operands are indexes 0-F.
+// Instructions print and parse as assembly in the mnemonics from Cowgod's
+// reference, with `DW` for opcodes that aren't anything.
+
+use core::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction {
+    // 00E0
+    Clear,
+    // 00EE
+    Return,
+    // 1NNN
+    Jump(u16),
+    // 2NNN
+    Call(u16),
+    // 3XNN
+    SkipIfEqual(u8, u8),
+    // 4XNN
+    SkipIfNotEqual(u8, u8),
+    // 5XY0
+    SkipIfRegistersEqual(u8, u8),
+    // 6XNN
+    Set(u8, u8),
+    // 7XNN
+    Add(u8, u8),
+    // 8XY0
+    Copy(u8, u8),
+    // 8XY1
+    Or(u8, u8),
+    // 8XY2
+    And(u8, u8),
+    // 8XY3
+    Xor(u8, u8),
+    // 8XY4
+    AddRegisters(u8, u8),
+    // 8XY5
+    Subtract(u8, u8),
+    // 8XY6
+    ShiftRight(u8, u8),
+    // 8XY7
+    SubtractReversed(u8, u8),
+    // 8XYE
+    ShiftLeft(u8, u8),
+    // 9XY0
+    SkipIfRegistersNotEqual(u8, u8),
+    // ANNN
+    SetIndex(u16),
+    // BNNN
+    JumpPlusV0(u16),
+    // CXNN
+    Random(u8, u8),
+    // DXYN
+    Draw(u8, u8, u8),
+    // EX9E
+    SkipIfKey(u8),
+    // EXA1
+    SkipIfNotKey(u8),
+    // FX07
+    GetDelay(u8),
+    // FX0A
+    WaitForKey(u8),
+    // FX15
+    SetDelay(u8),
+    // FX18
+    SetSound(u8),
+    // FX1E
+    AddIndex(u8),
+    // FX29
+    Font(u8),
+    // FX30
+    BigFont(u8),
+    // FX33
+    Bcd(u8),
+    // FX55
+    Store(u8),
+    // FX65
+    Load(u8),
+    // Anything else runs as a no-op.
+    Unknown(u16),
+}
+
+impl Instruction {
+    pub fn decode(opcode: u16) -> Instruction {
+        let x = ((opcode & 0x0F00) >> 8) as u8;
+        let y = ((opcode & 0x00F0) >> 4) as u8;
+        let n = (opcode & 0x000F) as u8;
+        let nn = (opcode & 0x00FF) as u8;
+        let nnn = opcode & 0x0FFF;
+
+        match (opcode >> 12, x, y, n) {
+            (0x0, 0x0, 0xE, 0x0) => Instruction::Clear,
+            (0x0, 0x0, 0xE, 0xE) => Instruction::Return,
+            (0x1, _, _, _) => Instruction::Jump(nnn),
+            (0x2, _, _, _) => Instruction::Call(nnn),
+            (0x3, _, _, _) => Instruction::SkipIfEqual(x, nn),
+            (0x4, _, _, _) => Instruction::SkipIfNotEqual(x, nn),
+            (0x5, _, _, 0x0) => Instruction::SkipIfRegistersEqual(x, y),
+            (0x6, _, _, _) => Instruction::Set(x, nn),
+            (0x7, _, _, _) => Instruction::Add(x, nn),
+            (0x8, _, _, 0x0) => Instruction::Copy(x, y),
+            (0x8, _, _, 0x1) => Instruction::Or(x, y),
+            (0x8, _, _, 0x2) => Instruction::And(x, y),
+            (0x8, _, _, 0x3) => Instruction::Xor(x, y),
+            (0x8, _, _, 0x4) => Instruction::AddRegisters(x, y),
+            (0x8, _, _, 0x5) => Instruction::Subtract(x, y),
+            (0x8, _, _, 0x6) => Instruction::ShiftRight(x, y),
+            (0x8, _, _, 0x7) => Instruction::SubtractReversed(x, y),
+            (0x8, _, _, 0xE) => Instruction::ShiftLeft(x, y),
+            (0x9, _, _, 0x0) => Instruction::SkipIfRegistersNotEqual(x, y),
+            (0xA, _, _, _) => Instruction::SetIndex(nnn),
+            (0xB, _, _, _) => Instruction::JumpPlusV0(nnn),
+            (0xC, _, _, _) => Instruction::Random(x, nn),
+            (0xD, _, _, _) => Instruction::Draw(x, y, n),
+            (0xE, _, 0x9, 0xE) => Instruction::SkipIfKey(x),
+            (0xE, _, 0xA, 0x1) => Instruction::SkipIfNotKey(x),
+            (0xF, _, 0x0, 0x7) => Instruction::GetDelay(x),
+            (0xF, _, 0x0, 0xA) => Instruction::WaitForKey(x),
+            (0xF, _, 0x1, 0x5) => Instruction::SetDelay(x),
+            (0xF, _, 0x1, 0x8) => Instruction::SetSound(x),
+            (0xF, _, 0x1, 0xE) => Instruction::AddIndex(x),
+            (0xF, _, 0x2, 0x9) => Instruction::Font(x),
+            (0xF, _, 0x3, 0x0) => Instruction::BigFont(x),
+            (0xF, _, 0x3, 0x3) => Instruction::Bcd(x),
+            (0xF, _, 0x5, 0x5) => Instruction::Store(x),
+            (0xF, _, 0x6, 0x5) => Instruction::Load(x),
+            _ => Instruction::Unknown(opcode),
+        }
+    }
+
+    // Whether this can send the program counter somewhere other than the next instruction,
+    // or write to memory that might hold code. A cached block always ends on one of these.
+    pub fn ends_block(&self) -> bool {
+        matches!(
+            self,
+            Instruction::Return
+                | Instruction::Jump(_)
+                | Instruction::Call(_)
+                | Instruction::SkipIfEqual(..)
+                | Instruction::SkipIfNotEqual(..)
+                | Instruction::SkipIfRegistersEqual(..)
+                | Instruction::SkipIfRegistersNotEqual(..)
+                | Instruction::JumpPlusV0(_)
+                | Instruction::SkipIfKey(_)
+                | Instruction::SkipIfNotKey(_)
+                | Instruction::WaitForKey(_)
+                | Instruction::Bcd(_)
+                | Instruction::Store(_)
+        )
+    }
+}
+
+impl Instruction {
+    pub fn encode(&self) -> u16 {
+        let xy = |op: u16, x: u8, y: u8, n: u16| op << 12 | u16::from(x) << 8 | u16::from(y) << 4 | n;
+        let xnn = |op: u16, x: u8, nn: u8| op << 12 | u16::from(x) << 8 | u16::from(nn);
+        match *self {
+            Instruction::Clear => 0x00E0,
+            Instruction::Return => 0x00EE,
+            Instruction::Jump(nnn) => 0x1000 | nnn,
+            Instruction::Call(nnn) => 0x2000 | nnn,
+            Instruction::SkipIfEqual(x, nn) => xnn(0x3, x, nn),
+            Instruction::SkipIfNotEqual(x, nn) => xnn(0x4, x, nn),
+            Instruction::SkipIfRegistersEqual(x, y) => xy(0x5, x, y, 0x0),
+            Instruction::Set(x, nn) => xnn(0x6, x, nn),
+            Instruction::Add(x, nn) => xnn(0x7, x, nn),
+            Instruction::Copy(x, y) => xy(0x8, x, y, 0x0),
+            Instruction::Or(x, y) => xy(0x8, x, y, 0x1),
+            Instruction::And(x, y) => xy(0x8, x, y, 0x2),
+            Instruction::Xor(x, y) => xy(0x8, x, y, 0x3),
+            Instruction::AddRegisters(x, y) => xy(0x8, x, y, 0x4),
+            Instruction::Subtract(x, y) => xy(0x8, x, y, 0x5),
+            Instruction::ShiftRight(x, y) => xy(0x8, x, y, 0x6),
+            Instruction::SubtractReversed(x, y) => xy(0x8, x, y, 0x7),
+            Instruction::ShiftLeft(x, y) => xy(0x8, x, y, 0xE),
+            Instruction::SkipIfRegistersNotEqual(x, y) => xy(0x9, x, y, 0x0),
+            Instruction::SetIndex(nnn) => 0xA000 | nnn,
+            Instruction::JumpPlusV0(nnn) => 0xB000 | nnn,
+            Instruction::Random(x, nn) => xnn(0xC, x, nn),
+            Instruction::Draw(x, y, n) => xy(0xD, x, y, n.into()),
+            Instruction::SkipIfKey(x) => xnn(0xE, x, 0x9E),
+            Instruction::SkipIfNotKey(x) => xnn(0xE, x, 0xA1),
+            Instruction::GetDelay(x) => xnn(0xF, x, 0x07),
+            Instruction::WaitForKey(x) => xnn(0xF, x, 0x0A),
+            Instruction::SetDelay(x) => xnn(0xF, x, 0x15),
+            Instruction::SetSound(x) => xnn(0xF, x, 0x18),
+            Instruction::AddIndex(x) => xnn(0xF, x, 0x1E),
+            Instruction::Font(x) => xnn(0xF, x, 0x29),
+            Instruction::BigFont(x) => xnn(0xF, x, 0x30),
+            Instruction::Bcd(x) => xnn(0xF, x, 0x33),
+            Instruction::Store(x) => xnn(0xF, x, 0x55),
+            Instruction::Load(x) => xnn(0xF, x, 0x65),
+            Instruction::Unknown(opcode) => opcode,
+        }
+    }
+
+    // One line of assembly, without comments. Case doesn't matter, numbers are
+    // decimal or hex with 0x or #. `DW` takes a whole opcode and decodes it.
+    pub fn parse(line: &str) -> Option<Instruction> {
+        let line = line.trim();
+        let (mnemonic, operands) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let mut parsed = [Operand::Missing; 3];
+        if !operands.trim().is_empty() {
+            let mut operands = operands.split(',');
+            for slot in parsed.iter_mut() {
+                match operands.next() {
+                    Some(operand) => *slot = Operand::parse(operand.trim())?,
+                    None => break,
+                }
+            }
+            if operands.next().is_some() {
+                return None;
+            }
+        }
+
+        use Operand::*;
+        let address = |n: u16| (n <= 0xFFF).then_some(n);
+        let byte = |n: u16| u8::try_from(n).ok();
+        let instruction = match (mnemonic.to_ascii_uppercase().as_str(), parsed) {
+            ("CLS", [Missing, Missing, Missing]) => Instruction::Clear,
+            ("RET", [Missing, Missing, Missing]) => Instruction::Return,
+            ("JP", [Number(n), Missing, Missing]) => Instruction::Jump(address(n)?),
+            ("JP", [Register(0), Number(n), Missing]) => Instruction::JumpPlusV0(address(n)?),
+            ("CALL", [Number(n), Missing, Missing]) => Instruction::Call(address(n)?),
+            ("SE", [Register(x), Number(n), Missing]) => Instruction::SkipIfEqual(x, byte(n)?),
+            ("SE", [Register(x), Register(y), Missing]) => Instruction::SkipIfRegistersEqual(x, y),
+            ("SNE", [Register(x), Number(n), Missing]) => Instruction::SkipIfNotEqual(x, byte(n)?),
+            ("SNE", [Register(x), Register(y), Missing]) => Instruction::SkipIfRegistersNotEqual(x, y),
+            ("LD", [Register(x), Number(n), Missing]) => Instruction::Set(x, byte(n)?),
+            ("LD", [Register(x), Register(y), Missing]) => Instruction::Copy(x, y),
+            ("LD", [I, Number(n), Missing]) => Instruction::SetIndex(address(n)?),
+            ("LD", [Register(x), DelayTimer, Missing]) => Instruction::GetDelay(x),
+            ("LD", [Register(x), Keypad, Missing]) => Instruction::WaitForKey(x),
+            ("LD", [DelayTimer, Register(x), Missing]) => Instruction::SetDelay(x),
+            ("LD", [SoundTimer, Register(x), Missing]) => Instruction::SetSound(x),
+            ("LD", [Font, Register(x), Missing]) => Instruction::Font(x),
+            ("LD", [BigFont, Register(x), Missing]) => Instruction::BigFont(x),
+            ("LD", [Bcd, Register(x), Missing]) => Instruction::Bcd(x),
+            ("LD", [AtI, Register(x), Missing]) => Instruction::Store(x),
+            ("LD", [Register(x), AtI, Missing]) => Instruction::Load(x),
+            ("ADD", [Register(x), Number(n), Missing]) => Instruction::Add(x, byte(n)?),
+            ("ADD", [Register(x), Register(y), Missing]) => Instruction::AddRegisters(x, y),
+            ("ADD", [I, Register(x), Missing]) => Instruction::AddIndex(x),
+            ("OR", [Register(x), Register(y), Missing]) => Instruction::Or(x, y),
+            ("AND", [Register(x), Register(y), Missing]) => Instruction::And(x, y),
+            ("XOR", [Register(x), Register(y), Missing]) => Instruction::Xor(x, y),
+            ("SUB", [Register(x), Register(y), Missing]) => Instruction::Subtract(x, y),
+            ("SHR", [Register(x), Register(y), Missing]) => Instruction::ShiftRight(x, y),
+            ("SUBN", [Register(x), Register(y), Missing]) => Instruction::SubtractReversed(x, y),
+            ("SHL", [Register(x), Register(y), Missing]) => Instruction::ShiftLeft(x, y),
+            ("RND", [Register(x), Number(n), Missing]) => Instruction::Random(x, byte(n)?),
+            ("DRW", [Register(x), Register(y), Number(n)]) if n <= 0xF => Instruction::Draw(x, y, n as u8),
+            ("SKP", [Register(x), Missing, Missing]) => Instruction::SkipIfKey(x),
+            ("SKNP", [Register(x), Missing, Missing]) => Instruction::SkipIfNotKey(x),
+            ("DW", [Number(n), Missing, Missing]) => Instruction::decode(n),
+            _ => return None,
+        };
+        Some(instruction)
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Instruction::Clear => write!(f, "CLS"),
+            Instruction::Return => write!(f, "RET"),
+            Instruction::Jump(nnn) => write!(f, "JP {:#05X}", nnn),
+            Instruction::Call(nnn) => write!(f, "CALL {:#05X}", nnn),
+            Instruction::SkipIfEqual(x, nn) => write!(f, "SE V{:X}, {:#04X}", x, nn),
+            Instruction::SkipIfNotEqual(x, nn) => write!(f, "SNE V{:X}, {:#04X}", x, nn),
+            Instruction::SkipIfRegistersEqual(x, y) => write!(f, "SE V{:X}, V{:X}", x, y),
+            Instruction::Set(x, nn) => write!(f, "LD V{:X}, {:#04X}", x, nn),
+            Instruction::Add(x, nn) => write!(f, "ADD V{:X}, {:#04X}", x, nn),
+            Instruction::Copy(x, y) => write!(f, "LD V{:X}, V{:X}", x, y),
+            Instruction::Or(x, y) => write!(f, "OR V{:X}, V{:X}", x, y),
+            Instruction::And(x, y) => write!(f, "AND V{:X}, V{:X}", x, y),
+            Instruction::Xor(x, y) => write!(f, "XOR V{:X}, V{:X}", x, y),
+            Instruction::AddRegisters(x, y) => write!(f, "ADD V{:X}, V{:X}", x, y),
+            Instruction::Subtract(x, y) => write!(f, "SUB V{:X}, V{:X}", x, y),
+            Instruction::ShiftRight(x, y) => write!(f, "SHR V{:X}, V{:X}", x, y),
+            Instruction::SubtractReversed(x, y) => write!(f, "SUBN V{:X}, V{:X}", x, y),
+            Instruction::ShiftLeft(x, y) => write!(f, "SHL V{:X}, V{:X}", x, y),
+            Instruction::SkipIfRegistersNotEqual(x, y) => write!(f, "SNE V{:X}, V{:X}", x, y),
+            Instruction::SetIndex(nnn) => write!(f, "LD I, {:#05X}", nnn),
+            Instruction::JumpPlusV0(nnn) => write!(f, "JP V0, {:#05X}", nnn),
+            Instruction::Random(x, nn) => write!(f, "RND V{:X}, {:#04X}", x, nn),
+            Instruction::Draw(x, y, n) => write!(f, "DRW V{:X}, V{:X}, {}", x, y, n),
+            Instruction::SkipIfKey(x) => write!(f, "SKP V{:X}", x),
+            Instruction::SkipIfNotKey(x) => write!(f, "SKNP V{:X}", x),
+            Instruction::GetDelay(x) => write!(f, "LD V{:X}, DT", x),
+            Instruction::WaitForKey(x) => write!(f, "LD V{:X}, K", x),
+            Instruction::SetDelay(x) => write!(f, "LD DT, V{:X}", x),
+            Instruction::SetSound(x) => write!(f, "LD ST, V{:X}", x),
+            Instruction::AddIndex(x) => write!(f, "ADD I, V{:X}", x),
+            Instruction::Font(x) => write!(f, "LD F, V{:X}", x),
+            Instruction::BigFont(x) => write!(f, "LD HF, V{:X}", x),
+            Instruction::Bcd(x) => write!(f, "LD B, V{:X}", x),
+            Instruction::Store(x) => write!(f, "LD [I], V{:X}", x),
+            Instruction::Load(x) => write!(f, "LD V{:X}, [I]", x),
+            Instruction::Unknown(opcode) => write!(f, "DW {:#06X}", opcode),
+        }
+    }
+}
+
+// An operand in a line of assembly, Missing for the ones that aren't there.
+#[derive(Clone, Copy)]
+enum Operand {
+    Missing,
+    Register(u8),
+    Number(u16),
+    I,
+    AtI,
+    DelayTimer,
+    SoundTimer,
+    Keypad,
+    Font,
+    BigFont,
+    Bcd,
+}
+
+impl Operand {
+    fn parse(operand: &str) -> Option<Operand> {
+        let operand = match operand.to_ascii_uppercase().as_str() {
+            "I" => Operand::I,
+            "[I]" => Operand::AtI,
+            "DT" => Operand::DelayTimer,
+            "ST" => Operand::SoundTimer,
+            "K" => Operand::Keypad,
+            "F" => Operand::Font,
+            "HF" => Operand::BigFont,
+            "B" => Operand::Bcd,
+            register if register.len() == 2 && register.starts_with('V') => {
+                Operand::Register(u8::from_str_radix(&register[1..], 16).ok()?)
+            }
+            _ => Operand::Number(parse_number(operand)?),
+        };
+        Some(operand)
+    }
+}
+
+// Decimal, or hex after 0x or #.
+pub(crate) fn parse_number(number: &str) -> Option<u16> {
+    let hex = number.strip_prefix("0x").or_else(|| number.strip_prefix("0X")).or_else(|| number.strip_prefix('#'));
+    match hex {
+        Some(hex) => u16::from_str_radix(hex, 16).ok(),
+        None => number.parse().ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_operands() {
+        assert_eq!(Instruction::decode(0x00E0), Instruction::Clear);
+        assert_eq!(Instruction::decode(0x1ABC), Instruction::Jump(0xABC));
+        assert_eq!(Instruction::decode(0x3A42), Instruction::SkipIfEqual(0xA, 0x42));
+        assert_eq!(Instruction::decode(0x8AB4), Instruction::AddRegisters(0xA, 0xB));
+        assert_eq!(Instruction::decode(0xD12F), Instruction::Draw(0x1, 0x2, 0xF));
+        assert_eq!(Instruction::decode(0xF30A), Instruction::WaitForKey(0x3));
+    }
+
+    #[test]
+    fn every_opcode_prints_and_parses_back() {
+        for opcode in 0..=u16::MAX {
+            let instruction = Instruction::decode(opcode);
+            assert_eq!(instruction.encode(), opcode);
+            let text = instruction.to_string();
+            assert_eq!(Instruction::parse(&text), Some(instruction), "{}", text);
+        }
+    }
+
+    #[test]
+    fn parses_assembly() {
+        assert_eq!(Instruction::parse("ld v3, #2a"), Some(Instruction::Set(0x3, 0x2A)));
+        assert_eq!(Instruction::parse("  DRW V0, VF, 15 "), Some(Instruction::Draw(0x0, 0xF, 0xF)));
+        assert_eq!(Instruction::parse("JP V0, 0x300"), Some(Instruction::JumpPlusV0(0x300)));
+        assert_eq!(Instruction::parse("DW 0x00E0"), Some(Instruction::Clear));
+        assert_eq!(Instruction::Store(0x5).to_string(), "LD [I], V5");
+        for line in ["", "CLS V0", "LD V0, 0x100", "JP V1, 0x200", "JP 0x1000", "DRW V0, V1, 16", "LD V0, V1, V2", "ADD V10, 1", "NOP"] {
+            assert_eq!(Instruction::parse(line), None, "{}", line);
+        }
+    }
+
+    #[test]
+    fn unknown_opcodes_are_kept() {
+        for opcode in [0x0000, 0x00FF, 0x5001, 0x8008, 0x9001, 0xE000, 0xF0FF] {
+            assert_eq!(Instruction::decode(opcode), Instruction::Unknown(opcode));
+        }
+    }
+}
@@ -0,0 +1,215 @@
+// Compiles the run of register-only instructions at the start of a cached block
+// to x86-64 code. Everything else (draws, key waits, memory, control flow) still
+// goes through `Chip8::execute`, and blocks that get written over are thrown away
+// by the block cache along with their code.
+
+use dynasmrt::{dynasm, AssemblyOffset, DynasmApi, ExecutableBuffer};
+
+use crate::Instruction;
+
+// Shorter runs aren't worth the call.
+const MIN_RUN: usize = 2;
+
+// The compiled code takes pointers to V0-VF and I.
+type Entry = extern "sysv64" fn(*mut u8, *mut u16);
+
+pub struct Compiled {
+    buffer: ExecutableBuffer,
+    entry: AssemblyOffset,
+    len: usize,
+}
+
+impl Compiled {
+    // How many instructions from the start of the block this covers.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn run(&self, vr: &mut [u8; 16], ir: &mut u16) {
+        // Safe as long as the buffer lives, the code only touches the 16 registers and I.
+        let entry: Entry = unsafe { core::mem::transmute(self.buffer.ptr(self.entry)) };
+        entry(vr.as_mut_ptr(), ir);
+    }
+}
+
+fn compiles(instruction: &Instruction) -> bool {
+    matches!(
+        instruction,
+        Instruction::Set(..)
+            | Instruction::Add(..)
+            | Instruction::Copy(..)
+            | Instruction::Or(..)
+            | Instruction::And(..)
+            | Instruction::Xor(..)
+            | Instruction::AddRegisters(..)
+            | Instruction::Subtract(..)
+            | Instruction::ShiftRight(..)
+            | Instruction::SubtractReversed(..)
+            | Instruction::ShiftLeft(..)
+            | Instruction::SetIndex(_)
+    )
+}
+
+// Returns None when the block doesn't start with enough instructions we can compile,
+// or the code buffer can't be mapped.
+pub fn compile(instructions: &[Instruction]) -> Option<Compiled> {
+    let len = instructions.iter().take_while(|instruction| compiles(instruction)).count();
+    if len < MIN_RUN {
+        return None;
+    }
+    let mut ops = dynasmrt::x64::Assembler::new().ok()?;
+    let entry = ops.offset();
+    // rdi points at V0, rsi at I. al and cl are scratch.
+    for instruction in &instructions[..len] {
+        match *instruction {
+            Instruction::Set(x, nn) => dynasm!(ops
+                ; .arch x64
+                ; mov BYTE [rdi + x as i32], nn as i8
+            ),
+            Instruction::Add(x, nn) => dynasm!(ops
+                ; .arch x64
+                ; add BYTE [rdi + x as i32], nn as i8
+            ),
+            Instruction::Copy(x, y) => dynasm!(ops
+                ; .arch x64
+                ; mov al, [rdi + y as i32]
+                ; mov [rdi + x as i32], al
+            ),
+            Instruction::Or(x, y) => dynasm!(ops
+                ; .arch x64
+                ; mov al, [rdi + y as i32]
+                ; or [rdi + x as i32], al
+            ),
+            Instruction::And(x, y) => dynasm!(ops
+                ; .arch x64
+                ; mov al, [rdi + y as i32]
+                ; and [rdi + x as i32], al
+            ),
+            Instruction::Xor(x, y) => dynasm!(ops
+                ; .arch x64
+                ; mov al, [rdi + y as i32]
+                ; xor [rdi + x as i32], al
+            ),
+            // VF is written after VX, so it wins when X is F, same as the interpreter.
+            Instruction::AddRegisters(x, y) => dynasm!(ops
+                ; .arch x64
+                ; mov al, [rdi + x as i32]
+                ; add al, [rdi + y as i32]
+                ; setc cl
+                ; mov [rdi + x as i32], al
+                ; mov [rdi + 0xF], cl
+            ),
+            Instruction::Subtract(x, y) => dynasm!(ops
+                ; .arch x64
+                ; mov al, [rdi + x as i32]
+                ; sub al, [rdi + y as i32]
+                ; setnc cl
+                ; mov [rdi + x as i32], al
+                ; mov [rdi + 0xF], cl
+            ),
+            Instruction::SubtractReversed(x, y) => dynasm!(ops
+                ; .arch x64
+                ; mov al, [rdi + y as i32]
+                ; sub al, [rdi + x as i32]
+                ; setnc cl
+                ; mov [rdi + x as i32], al
+                ; mov [rdi + 0xF], cl
+            ),
+            Instruction::ShiftRight(x, _) => dynasm!(ops
+                ; .arch x64
+                ; mov al, [rdi + x as i32]
+                ; mov cl, al
+                ; and cl, 1
+                ; shr al, 1
+                ; mov [rdi + x as i32], al
+                ; mov [rdi + 0xF], cl
+            ),
+            Instruction::ShiftLeft(x, _) => dynasm!(ops
+                ; .arch x64
+                ; mov al, [rdi + x as i32]
+                ; mov cl, al
+                ; shr cl, 7
+                ; shl al, 1
+                ; mov [rdi + x as i32], al
+                ; mov [rdi + 0xF], cl
+            ),
+            Instruction::SetIndex(nnn) => dynasm!(ops
+                ; .arch x64
+                ; mov WORD [rsi], nnn as i16
+            ),
+            _ => unreachable!("only compiling register instructions"),
+        }
+    }
+    dynasm!(ops
+        ; .arch x64
+        ; ret
+    );
+    let buffer = ops.finalize().ok()?;
+    Some(Compiled { buffer, entry, len })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::tests::assert_random_programs_agree;
+    use crate::Engine;
+
+    #[test]
+    fn compiles_the_leading_register_run() {
+        let instructions = [
+            Instruction::Set(0, 200),
+            Instruction::Set(1, 100),
+            Instruction::AddRegisters(0, 1),
+            Instruction::SetIndex(0xABC),
+            Instruction::Draw(0, 0, 5),
+            Instruction::Set(2, 1),
+        ];
+        let compiled = compile(&instructions).unwrap();
+        assert_eq!(compiled.len(), 4);
+        let mut vr = [0; 16];
+        let mut ir = 0;
+        compiled.run(&mut vr, &mut ir);
+        assert_eq!(vr[0], 44);
+        assert_eq!(vr[0xF], 1);
+        assert_eq!(vr[2], 0);
+        assert_eq!(ir, 0xABC);
+    }
+
+    #[test]
+    fn short_runs_are_left_alone() {
+        assert!(compile(&[Instruction::Set(0, 1), Instruction::Jump(0x200)]).is_none());
+        assert!(compile(&[Instruction::Draw(0, 0, 1)]).is_none());
+    }
+
+    #[test]
+    fn flags_match_the_interpreter() {
+        let mut rng = crate::rng::Rng::new(99);
+        for _ in 0..1000 {
+            let x = rng.next_u8() & 0xF;
+            let y = rng.next_u8() & 0xF;
+            let instruction = match rng.next_u8() % 5 {
+                0 => Instruction::AddRegisters(x, y),
+                1 => Instruction::Subtract(x, y),
+                2 => Instruction::SubtractReversed(x, y),
+                3 => Instruction::ShiftRight(x, y),
+                _ => Instruction::ShiftLeft(x, y),
+            };
+            let mut vr = [0u8; 16];
+            vr.iter_mut().for_each(|v| *v = rng.next_u8());
+            let mut chip8 = crate::Chip8::new(crate::Options::new()).unwrap();
+            chip8.vr = vr;
+            chip8.execute(instruction).unwrap();
+            chip8.execute(Instruction::Copy(0, 0)).unwrap();
+
+            let compiled = compile(&[instruction, Instruction::Copy(0, 0)]).unwrap();
+            let mut ir = 0;
+            compiled.run(&mut vr, &mut ir);
+            assert_eq!(vr, chip8.vr, "{:?}", instruction);
+        }
+    }
+
+    #[test]
+    fn random_programs_match_the_interpreter() {
+        assert_random_programs_agree(Engine::Jit);
+    }
+}
@@ -0,0 +1,112 @@
+// Bindings from whatever a frontend reads (keyboard keys, characters, gamepad
+// buttons) to keys on the hex keypad. Several inputs can share a key.
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+
+use crate::Key;
+
+// The usual layout, the left side of a QWERTY keyboard standing in for the
+// COSMAC VIP's hex keypad:
+//     1 2 3 4      1 2 3 C
+//     Q W E R  ->  4 5 6 D
+//     A S D F      7 8 9 E
+//     Z X C V      A 0 B F
+pub const QWERTY: [(char, Key); 16] = [
+    ('1', Key::K1), ('2', Key::K2), ('3', Key::K3), ('4', Key::KC),
+    ('q', Key::K4), ('w', Key::K5), ('e', Key::K6), ('r', Key::KD),
+    ('a', Key::K7), ('s', Key::K8), ('d', Key::K9), ('f', Key::KE),
+    ('z', Key::KA), ('x', Key::K0), ('c', Key::KB), ('v', Key::KF),
+];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Keymap<I> {
+    bindings: BTreeMap<I, Key>,
+}
+
+impl<I: Ord> Keymap<I> {
+    pub fn new() -> Keymap<I> {
+        Keymap { bindings: BTreeMap::new() }
+    }
+
+    // The QWERTY layout, for frontends that can turn a character into one of their keys.
+    pub fn qwerty(input: impl Fn(char) -> Option<I>) -> Keymap<I> {
+        QWERTY.iter().filter_map(|&(c, key)| Some((input(c)?, key))).collect()
+    }
+
+    // Replaces whatever the input was bound to before.
+    pub fn bind(&mut self, input: I, key: Key) {
+        self.bindings.insert(input, key);
+    }
+
+    pub fn unbind(&mut self, input: &I) {
+        self.bindings.remove(input);
+    }
+
+    pub fn get(&self, input: &I) -> Option<Key> {
+        self.bindings.get(input).copied()
+    }
+
+    pub fn bindings(&self) -> impl Iterator<Item = (&I, Key)> + '_ {
+        self.bindings.iter().map(|(input, &key)| (input, key))
+    }
+
+    // Binds a space separated list like "up:5 down:8 south:6" on top of what's
+    // already there, with the names going through `input`. "name:-" unbinds one.
+    pub fn parse_into(&mut self, text: &str, input: impl Fn(&str) -> Option<I>) -> Result<(), String> {
+        for binding in text.split_whitespace() {
+            let invalid = || format!("Invalid binding \"{}\", expected name:key like up:5.", binding);
+            let (name, key) = binding.split_once(':').ok_or_else(invalid)?;
+            let name = input(name).ok_or_else(|| format!("Nothing called \"{}\" to bind.", name))?;
+            if key == "-" {
+                self.unbind(&name);
+                continue;
+            }
+            let key = u8::from_str_radix(key, 16).ok().and_then(Key::new).ok_or_else(invalid)?;
+            self.bind(name, key);
+        }
+        Ok(())
+    }
+}
+
+impl<I: Ord> Default for Keymap<I> {
+    fn default() -> Keymap<I> {
+        Keymap::new()
+    }
+}
+
+impl<I: Ord> FromIterator<(I, Key)> for Keymap<I> {
+    fn from_iter<T: IntoIterator<Item = (I, Key)>>(iter: T) -> Keymap<I> {
+        Keymap { bindings: iter.into_iter().collect() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn qwerty_covers_the_keypad() {
+        let keymap = Keymap::qwerty(Some);
+        assert_eq!(keymap.get(&'w'), Some(Key::K5));
+        assert_eq!(keymap.get(&'x'), Some(Key::K0));
+        assert_eq!(keymap.get(&'p'), None);
+        let mut keys: alloc::vec::Vec<Key> = keymap.bindings().map(|(_, key)| key).collect();
+        keys.sort();
+        assert_eq!(keys, Key::ALL);
+    }
+
+    #[test]
+    fn parse_binds_on_top() {
+        let mut keymap = Keymap::qwerty(Some);
+        let one_char = |name: &str| name.chars().next().filter(|_| name.len() == 1);
+        keymap.parse_into("w:2  k:a x:-", one_char).unwrap();
+        assert_eq!(keymap.get(&'w'), Some(Key::K2));
+        assert_eq!(keymap.get(&'k'), Some(Key::KA));
+        assert_eq!(keymap.get(&'x'), None);
+        assert!(keymap.parse_into("w:10", one_char).is_err());
+        assert!(keymap.parse_into("w5", one_char).is_err());
+        assert!(keymap.parse_into("up:5", one_char).is_err());
+    }
+}
@@ -0,0 +1,314 @@
+// The COSMAC VIP's 16 key hex keypad. Keys are a typed `Key` everywhere outside
+// the interpreter so a bad index can't get in from a frontend; ROMs can still ask
+// for keys past F, which is what `KeyPolicy` is for.
+
+use alloc::collections::{BTreeMap, VecDeque};
+use core::fmt;
+
+use crate::{InputSource, KeyEvent};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Key {
+    K0,
+    K1,
+    K2,
+    K3,
+    K4,
+    K5,
+    K6,
+    K7,
+    K8,
+    K9,
+    KA,
+    KB,
+    KC,
+    KD,
+    KE,
+    KF,
+}
+
+impl Key {
+    pub const ALL: [Key; 16] = [
+        Key::K0, Key::K1, Key::K2, Key::K3, Key::K4, Key::K5, Key::K6, Key::K7,
+        Key::K8, Key::K9, Key::KA, Key::KB, Key::KC, Key::KD, Key::KE, Key::KF,
+    ];
+
+    // None past F.
+    pub fn new(index: u8) -> Option<Key> {
+        Key::ALL.get(index as usize).copied()
+    }
+
+    // Only looks at the low nibble.
+    pub fn from_nibble(value: u8) -> Key {
+        Key::ALL[(value & 0xF) as usize]
+    }
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    fn bit(self) -> u16 {
+        1 << self as u16
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:X}", self.index())
+    }
+}
+
+// What EX9E and EXA1 do when VX is past F.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KeyPolicy {
+    // Only the low nibble counts, the VIP only ever looked at 4 bits.
+    #[default]
+    Mask,
+    // There's no such key, so it's never down.
+    Ignore,
+    // Stop the interpreter.
+    Error,
+}
+
+// Which keys are down, and which went down, came up or were checked by the ROM
+// since the edges were last cleared. `Chip8::run_frame` clears them at the start
+// of every frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Keypad {
+    down: u16,
+    pressed: u16,
+    released: u16,
+    polled: u16,
+}
+
+impl Keypad {
+    pub fn new() -> Keypad {
+        Keypad::default()
+    }
+
+    pub fn is_down(&self, key: Key) -> bool {
+        self.down & key.bit() != 0
+    }
+
+    pub fn was_pressed(&self, key: Key) -> bool {
+        self.pressed & key.bit() != 0
+    }
+
+    pub fn was_released(&self, key: Key) -> bool {
+        self.released & key.bit() != 0
+    }
+
+    // Whether EX9E/EXA1 looked at the key, or FX0A was waiting for any key.
+    pub fn was_polled(&self, key: Key) -> bool {
+        self.polled & key.bit() != 0
+    }
+
+    pub fn down_keys(&self) -> impl Iterator<Item = Key> + '_ {
+        Key::ALL.into_iter().filter(|&key| self.is_down(key))
+    }
+
+    // Every press since the edges were cleared, then every release. A key tapped
+    // within a frame shows up in both.
+    pub fn edges(&self) -> impl Iterator<Item = KeyEvent> + '_ {
+        let presses = Key::ALL.into_iter().filter(|&key| self.was_pressed(key)).map(|key| KeyEvent::new(key, true));
+        let releases = Key::ALL.into_iter().filter(|&key| self.was_released(key)).map(|key| KeyEvent::new(key, false));
+        presses.chain(releases)
+    }
+
+    // Returns the edge, or None if the key was already that way.
+    pub fn set(&mut self, key: Key, pressed: bool) -> Option<KeyEvent> {
+        if self.is_down(key) == pressed {
+            return None;
+        }
+        if pressed {
+            self.down |= key.bit();
+            self.pressed |= key.bit();
+        } else {
+            self.down &= !key.bit();
+            self.released |= key.bit();
+        }
+        Some(KeyEvent::new(key, pressed))
+    }
+
+    pub fn clear_edges(&mut self) {
+        self.pressed = 0;
+        self.released = 0;
+        self.polled = 0;
+    }
+
+    // Down, pressed, released and polled, for save states.
+    pub(crate) fn to_bits(self) -> [u16; 4] {
+        [self.down, self.pressed, self.released, self.polled]
+    }
+
+    pub(crate) fn from_bits([down, pressed, released, polled]: [u16; 4]) -> Keypad {
+        Keypad { down, pressed, released, polled }
+    }
+
+    // Whether the key is down, remembering that the ROM asked.
+    pub(crate) fn poll(&mut self, key: Key) -> bool {
+        self.polled |= key.bit();
+        self.is_down(key)
+    }
+
+    pub(crate) fn poll_all(&mut self) {
+        self.polled = u16::MAX;
+    }
+
+    // The lowest key pressed since the edges were cleared.
+    pub(crate) fn first_pressed(&self) -> Option<Key> {
+        Key::new(self.pressed.trailing_zeros() as u8)
+    }
+}
+
+// Keys held from several places at once: keyboard keys, gamepad buttons, touches.
+// Each holder presses one key, and the key only comes back up once the last of
+// its holders lets go. The presses and releases come out as an `InputSource`.
+#[derive(Clone, Debug)]
+pub struct HeldKeys<H> {
+    held: BTreeMap<H, Key>,
+    pending: VecDeque<KeyEvent>,
+}
+
+impl<H: Ord> HeldKeys<H> {
+    pub fn new() -> HeldKeys<H> {
+        HeldKeys { held: BTreeMap::new(), pending: VecDeque::new() }
+    }
+
+    pub fn is_held(&self, key: Key) -> bool {
+        self.held.values().any(|&held| held == key)
+    }
+
+    // Does nothing if `holder` already holds a key, so key repeat and remaps
+    // while held don't leave anything stuck down.
+    pub fn press(&mut self, holder: H, key: Key) {
+        if self.held.contains_key(&holder) {
+            return;
+        }
+        if !self.is_held(key) {
+            self.pending.push_back(KeyEvent::new(key, true));
+        }
+        self.held.insert(holder, key);
+    }
+
+    // Returns the key `holder` was holding, if any.
+    pub fn release(&mut self, holder: &H) -> Option<Key> {
+        let key = self.held.remove(holder)?;
+        if !self.is_held(key) {
+            self.pending.push_back(KeyEvent::new(key, false));
+        }
+        Some(key)
+    }
+
+    // Lets go for every holder `which` picks out.
+    pub fn release_all(&mut self, mut which: impl FnMut(&H) -> bool) {
+        let mut released = alloc::vec::Vec::new();
+        self.held.retain(|holder, key| {
+            let keep = !which(holder);
+            if !keep {
+                released.push(*key);
+            }
+            keep
+        });
+        released.sort();
+        released.dedup();
+        for key in released {
+            if !self.is_held(key) {
+                self.pending.push_back(KeyEvent::new(key, false));
+            }
+        }
+    }
+}
+
+impl<H: Ord> Default for HeldKeys<H> {
+    fn default() -> HeldKeys<H> {
+        HeldKeys::new()
+    }
+}
+
+impl<H: Ord> InputSource for HeldKeys<H> {
+    fn poll_key(&mut self) -> Option<KeyEvent> {
+        self.pending.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_from_indexes() {
+        assert_eq!(Key::new(0xA), Some(Key::KA));
+        assert_eq!(Key::new(0x10), None);
+        assert_eq!(Key::from_nibble(0x1A), Key::KA);
+        assert!(Key::ALL.iter().enumerate().all(|(i, key)| key.index() == i));
+        assert_eq!(Key::KB.to_string(), "B");
+    }
+
+    #[test]
+    fn edges_are_reported_once() {
+        let mut keypad = Keypad::new();
+        assert_eq!(keypad.set(Key::K5, true), Some(KeyEvent::new(Key::K5, true)));
+        assert_eq!(keypad.set(Key::K5, true), None);
+        assert!(keypad.is_down(Key::K5));
+        keypad.set(Key::K2, true);
+        keypad.set(Key::K2, false);
+        assert_eq!(
+            keypad.edges().collect::<alloc::vec::Vec<_>>(),
+            [KeyEvent::new(Key::K2, true), KeyEvent::new(Key::K5, true), KeyEvent::new(Key::K2, false)]
+        );
+        assert_eq!(keypad.first_pressed(), Some(Key::K2));
+        keypad.clear_edges();
+        assert_eq!(keypad.edges().count(), 0);
+        assert_eq!(keypad.first_pressed(), None);
+        assert_eq!(keypad.down_keys().collect::<alloc::vec::Vec<_>>(), [Key::K5]);
+    }
+
+    #[test]
+    fn polls_last_until_the_edges_are_cleared() {
+        let mut keypad = Keypad::new();
+        keypad.set(Key::K3, true);
+        assert!(keypad.poll(Key::K3));
+        assert!(!keypad.poll(Key::K4));
+        assert!(keypad.was_polled(Key::K4));
+        assert!(!keypad.was_polled(Key::K5));
+        keypad.clear_edges();
+        assert!(!keypad.was_polled(Key::K3));
+        keypad.poll_all();
+        assert!(Key::ALL.iter().all(|&key| keypad.was_polled(key)));
+    }
+
+    fn drain(held: &mut HeldKeys<&str>) -> alloc::vec::Vec<KeyEvent> {
+        core::iter::from_fn(|| held.poll_key()).collect()
+    }
+
+    #[test]
+    fn keys_stay_down_until_the_last_holder_lets_go() {
+        let mut held = HeldKeys::new();
+        held.press("keyboard", Key::K5);
+        held.press("pad", Key::K5);
+        held.press("keyboard", Key::K5);
+        assert_eq!(drain(&mut held), [KeyEvent::new(Key::K5, true)]);
+        assert_eq!(held.release(&"keyboard"), Some(Key::K5));
+        assert_eq!(held.release(&"keyboard"), None);
+        assert!(held.is_held(Key::K5));
+        assert_eq!(drain(&mut held), []);
+        held.release(&"pad");
+        assert_eq!(drain(&mut held), [KeyEvent::new(Key::K5, false)]);
+    }
+
+    #[test]
+    fn releasing_a_group_of_holders() {
+        let mut held = HeldKeys::new();
+        held.press("mouse", Key::K1);
+        held.press("touch", Key::K1);
+        held.press("touch 2", Key::K2);
+        held.press("keyboard", Key::K2);
+        drain(&mut held);
+        held.release_all(|holder| holder.starts_with("touch") || *holder == "mouse");
+        assert_eq!(drain(&mut held), [KeyEvent::new(Key::K1, false)]);
+        assert!(held.is_held(Key::K2));
+    }
+}
@@ -1,220 +1,318 @@
+// The interpreter only needs an allocator. Post-processing, screenshots and
+// recording need std and are left out without the std feature.
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
+
+extern crate alloc;
+
+mod asm;
+mod cache;
+#[cfg(feature = "std")]
+mod crt;
 mod display;
+#[cfg(feature = "embedded")]
+mod embedded;
+mod error;
+mod font;
+#[cfg(feature = "std")]
+mod frame;
+mod gamepad;
+mod host;
+mod instruction;
+mod keymap;
+mod keypad;
+#[cfg(all(feature = "jit", target_arch = "x86_64"))]
+mod jit;
+mod memory;
+#[cfg(test)]
+mod opcode_tests;
 mod options;
+mod overlay;
+mod palette;
+#[cfg(feature = "std")]
+mod phosphor;
+mod quirks;
+#[cfg(feature = "std")]
+mod recorder;
+mod rng;
+#[cfg(feature = "std")]
+mod screenshot;
+#[cfg(feature = "window")]
+mod shader;
+mod speed;
+mod stack;
+mod state;
 
+pub use asm::{assemble, disassemble};
+pub use cache::{Block, BlockCache, Engine};
+#[cfg(feature = "std")]
+pub use crt::{apply as apply_crt_effects, CrtEffects};
 pub use display::Display;
+#[cfg(feature = "embedded")]
+pub use embedded::GraphicsSink;
+pub use error::Error;
+pub use font::{BigFont, Font};
+#[cfg(feature = "std")]
+pub use frame::compose_frame;
+#[cfg(feature = "window")]
+pub use frame::upload_frame;
+pub use gamepad::{GamepadBackend, Gamepads, PadButton, PadEvent, PadId, VirtualGamepad};
+pub use host::{AudioSink, Clock, Host, InputSource, KeyEvent, VideoSink};
+pub use instruction::Instruction;
+pub use keymap::{Keymap, QWERTY};
+pub use keypad::{HeldKeys, Key, KeyPolicy, Keypad};
+pub use memory::{Memory, MemoryPolicy};
 pub use options::Options;
+pub use overlay::{KeypadOverlay, OverlayMode};
+pub use palette::Palette;
+#[cfg(feature = "std")]
+pub use phosphor::{Persistence, Phosphor};
+pub use quirks::{KeyWait, Quirks, SpriteEdge};
+#[cfg(feature = "std")]
+pub use recorder::{GifRecorder, RawRecorder, Recorder};
+#[cfg(feature = "std")]
+pub use screenshot::{encode_png, save_png, save_screenshot, timestamped_path};
+#[cfg(feature = "window")]
+pub use shader::CrtRenderer;
+pub use speed::{draw_status, Speed};
+pub use stack::{Stack, StackPolicy};
+
+use alloc::vec::Vec;
+use core::time::Duration;
 
-use std::time::{Duration, Instant};
+use rng::Rng;
 
-const RAM: usize = 65536;
-const TIMER_RATE: u64 = 16666; //60Hz
+const TIMER_RATE: Duration = Duration::from_micros(16666); //60Hz
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum KeyWaitState {
+    Idle,
+    Waiting,
+    Held(Key),
+}
 
 pub struct Chip8 {
     pc: u16,
     ir: u16,
     vr: [u8; 16],
-    sp: usize,
-    stack: [u16; 16],
-    memory: [u8; RAM],
+    stack: Stack,
+    memory: Memory,
     display: Display,
     delay_timer: u8,
     sound_timer: u8,
     options: Options,
-    last_tick: Instant,
-    keyboard: [bool; 16],
+    rng: Rng,
+    // Only filled in with the cached engine.
+    cache: BlockCache,
+    // Instructions run since the interpreter started.
+    instructions: u64,
+    // When the timers last ticked, as passed to `run_cpu_cycle`.
+    last_tick: Option<Duration>,
+    keypad: Keypad,
+    // The same keys, with edges since FX0A started waiting rather than since the frame
+    // started, so the interpreter never touches what frontends see.
+    key_wait_edges: Keypad,
+    key_wait: KeyWaitState,
+    // Set by DXYN when the display wait quirk is on, cleared at the start of the next frame.
+    waiting_for_vblank: bool,
 }
 
 impl Chip8 {
-    pub fn new(options: Options) -> Chip8 {
-        // todo!();
-        let mut memory = [0; RAM];
-
-        let font = options.font();
+    // Fails if either font doesn't fit below the program at 0x200, the two fonts
+    // overlap, or the stack is deeper than `Stack::MAX_DEPTH`.
+    pub fn new(options: Options) -> Result<Chip8, Error> {
+        if let Some(depth) = options.stack_depth().filter(|&depth| depth > Stack::MAX_DEPTH) {
+            return Err(Error::StackTooDeep(depth));
+        }
+        let mut memory = Memory::new(options.memory_size(), options.memory_policy());
 
-        memory[0x50 .. (0x50 + font.len())].clone_from_slice(&font);
-        Chip8 {
+        load_font(&mut memory, options.font_address(), options.font().data())?;
+        load_font(&mut memory, options.big_font_address(), options.big_font().data())?;
+        let (font, big_font) = (options.font_address() as usize, options.big_font_address() as usize);
+        if font < big_font + options.big_font().data().len() && big_font < font + options.font().data().len() {
+            return Err(Error::FontsOverlap { font, big_font });
+        }
+        let mut display = Display::new();
+        display.set_sprite_edge(options.quirks().sprite_edge());
+        Ok(Chip8 {
             pc: 0x200,
             ir: 0,
             vr: [0; 16],
-            sp: 0,
-            stack: [0; 16],
-            memory: memory,
-            display: Display::new(),
+            stack: Stack::new(options.stack_depth(), options.stack_policy()),
+            memory,
+            display,
             delay_timer: 0,
             sound_timer: 0,
-            options: options,
-            last_tick: Instant::now(),
-            keyboard: [false; 16],
-        }
+            cache: BlockCache::new(),
+            instructions: 0,
+            rng: options.seed().map_or_else(Rng::from_entropy, Rng::new),
+            options,
+            last_tick: None,
+            keypad: Keypad::new(),
+            key_wait_edges: Keypad::new(),
+            key_wait: KeyWaitState::Idle,
+            waiting_for_vblank: false,
+        })
     }
 
     pub fn display(&self) -> &Display {
         &self.display
     }
 
-    pub fn display_mut(&mut self) -> &mut Display {
-        &mut self.display
+    pub fn stack(&self) -> &Stack {
+        &self.stack
+    }
+
+    pub fn memory(&self) -> &Memory {
+        &self.memory
+    }
+
+    pub fn instructions(&self) -> u64 {
+        self.instructions
+    }
+
+    pub fn load_rom(&mut self, rom: Vec<u8>) -> Result<(), Error> {
+        self.cache.clear();
+        self.memory.load(0x200, rom.as_slice())
     }
 
-    pub fn load_rom(&mut self, rom: Vec<u8>) {
-        self.memory[0x200..][..rom.len()].copy_from_slice(rom.as_slice());
+    pub fn keypad(&self) -> &Keypad {
+        &self.keypad
     }
 
-    pub fn set_key(&mut self, key: usize, pressed: bool) -> bool {
-        self.keyboard[key] = pressed;
-        true
+    // Returns the edge, or None if the key was already that way.
+    pub fn set_key(&mut self, key: Key, pressed: bool) -> Option<KeyEvent> {
+        self.key_wait_edges.set(key, pressed);
+        self.keypad.set(key, pressed)
     }
 
-    pub fn fetch(&mut self) -> u16 {
-        // What SHOULD happen when we read the end of memory???
-        // wrap back to the beginning???
-        let opcode = (u16::from(self.memory[self.pc as usize]) << 8)
-            | u16::from(self.memory[self.pc.wrapping_add(1) as usize]);
+    pub fn fetch(&mut self) -> Result<u16, Error> {
+        // Reading past the end of memory wraps or errors depending on the memory policy.
+        let opcode = (u16::from(self.memory.read(self.pc as usize)?) << 8)
+            | u16::from(self.memory.read(self.pc as usize + 1)?);
         self.pc = self.pc.wrapping_add(2);
-        opcode
-    }
-
-    pub fn decode(&mut self, opcode: u16) -> Result<(), String> {
-        let x = ((opcode & 0x0F00) >> 8) as usize;
-        let y = ((opcode & 0x00F0) >> 4) as usize;
-        let n = opcode & 0x000F;
-        let nn = (opcode & 0x00FF) as u8;
-        let nnn = opcode & 0x0FFF;
-
-        let nib_1 = (opcode & 0xF000) >> 12;
-        let nib_2 = (opcode & 0x0F00) >> 8;
-        let nib_3 = (opcode & 0x00F0) >> 4;
-        let nib_4 = opcode & 0x000F;
-
-        match (nib_1, nib_2, nib_3, nib_4) {
-            (0x0, 0x0, 0xE, 0x0) => self.display.clear_screen(),
-            (0x0, 0x0, 0xE, 0xE) => {
-                if self.sp == 0 {
-                    return Err(String::from("Attempted to pop from empty stack."));
+        Ok(opcode)
+    }
+
+    pub fn decode(&mut self, opcode: u16) -> Result<(), Error> {
+        self.execute(Instruction::decode(opcode))
+    }
+
+    pub fn execute(&mut self, instruction: Instruction) -> Result<(), Error> {
+        match instruction {
+            Instruction::Clear => self.display.clear_screen(),
+            Instruction::Return => {
+                if let Some(address) = self.stack.pop()? {
+                    self.pc = address;
                 }
-                self.pc = self.stack[self.sp];
-                self.sp -= 1;
             }
-            (0x1, _, _, _) => self.pc = nnn,
-            (0x2, _, _, _) => {
-                self.sp += 1;
-                if self.sp >= self.stack.len() {
-                    return Err(String::from("Stack limit reached."));
-                }
-                self.stack[self.sp] = self.pc;
+            Instruction::Jump(nnn) => self.pc = nnn,
+            Instruction::Call(nnn) => {
+                self.stack.push(self.pc)?;
                 self.pc = nnn;
             }
-            (0x3, _, _, _) => {
-                if self.vr[x] == nn {
-                    self.fetch();
-                }
+            Instruction::SkipIfEqual(x, nn) if self.vr[x as usize] == nn => {
+                self.fetch()?;
             }
-            (0x4, _, _, _) => {
-                if self.vr[x] != nn {
-                    self.fetch();
-                }
+            Instruction::SkipIfNotEqual(x, nn) if self.vr[x as usize] != nn => {
+                self.fetch()?;
             }
-            (0x5, _, _, 0x0) => {
-                if self.vr[x] == self.vr[y] {
-                    self.fetch();
-                }
+            Instruction::SkipIfRegistersEqual(x, y) if self.vr[x as usize] == self.vr[y as usize] => {
+                self.fetch()?;
             }
-            (0x6, _, _, _) => self.vr[x] = nn,
-            (0x7, _, _, _) => self.vr[x] = self.vr[x].wrapping_add(nn),
+            Instruction::Set(x, nn) => self.vr[x as usize] = nn,
+            Instruction::Add(x, nn) => self.vr[x as usize] = self.vr[x as usize].wrapping_add(nn),
             // Logical and Arithmetic Instructions
-            (0x8, _, _, 0x0) => self.vr[x] = self.vr[y],
-            (0x8, _, _, 0x1) => self.vr[x] |= self.vr[y],
-            (0x8, _, _, 0x2) => self.vr[x] &= self.vr[y],
-            (0x8, _, _, 0x3) => self.vr[x] ^= self.vr[y],
-            (0x8, _, _, 0x4) => {
-                let (result, carry) = self.vr[x].overflowing_add(self.vr[y]);
-                self.vr[x] = result;
+            Instruction::Copy(x, y) => self.vr[x as usize] = self.vr[y as usize],
+            Instruction::Or(x, y) => self.vr[x as usize] |= self.vr[y as usize],
+            Instruction::And(x, y) => self.vr[x as usize] &= self.vr[y as usize],
+            Instruction::Xor(x, y) => self.vr[x as usize] ^= self.vr[y as usize],
+            Instruction::AddRegisters(x, y) => {
+                let (result, carry) = self.vr[x as usize].overflowing_add(self.vr[y as usize]);
+                self.vr[x as usize] = result;
                 self.vr[0xF] = if carry {1} else {0};
             }
-            (0x8, _, _, 0x5) => {
-                let (result, carry) = self.vr[x].overflowing_sub(self.vr[y]);
-                self.vr[x] = result;
+            Instruction::Subtract(x, y) => {
+                let (result, carry) = self.vr[x as usize].overflowing_sub(self.vr[y as usize]);
+                self.vr[x as usize] = result;
                 self.vr[0xF] = if carry {0} else {1}; // VF set to 0 if underflow.
             }
-            (0x8, _, _, 0x6) => {
+            Instruction::ShiftRight(x, _y) => {
                 // Ambiguous instruction! TODO: option to switch implementations.
                 // self.vr[x] = self.vr[y]; // old behavior.
-                let f = self.vr[x] & 1;
-                self.vr[x] = self.vr[x] >> 1;
+                let f = self.vr[x as usize] & 1;
+                self.vr[x as usize] >>= 1;
                 self.vr[0xF] = f;
             }
-            (0x8, _, _, 0x7) => {
-                let (result, carry) = self.vr[y].overflowing_sub(self.vr[x]);
-                self.vr[x] = result;
+            Instruction::SubtractReversed(x, y) => {
+                let (result, carry) = self.vr[y as usize].overflowing_sub(self.vr[x as usize]);
+                self.vr[x as usize] = result;
                 self.vr[0xF] = if carry {0} else {1};
             }
-            (0x8, _, _, 0xE) => {
+            Instruction::ShiftLeft(x, _y) => {
                 // Ambiguous instruction! TODO: option to switch implementations.
                 // self.vr[x] = self.vr[y]; // old behavior.
-                let f = (self.vr[x] & 0x80) >> 7;
-                self.vr[x] = self.vr[x] << 1;
+                let f = (self.vr[x as usize] & 0x80) >> 7;
+                self.vr[x as usize] <<= 1;
                 self.vr[0xF] = f;
             }
-            (0x9, _, _, 0x0) => {
-                if self.vr[x] != self.vr[y] {
-                    self.fetch();
-                }
+            Instruction::SkipIfRegistersNotEqual(x, y) if self.vr[x as usize] != self.vr[y as usize] => {
+                self.fetch()?;
             }
-            (0xA, _, _, _) => self.ir = nnn,
-            (0xB, _, _, _) => {
+            Instruction::SetIndex(nnn) => self.ir = nnn,
+            Instruction::JumpPlusV0(nnn) => {
                 // Ambiguous instruction! TODO: option to switch implementations.
                 self.pc = nnn + u16::from(self.vr[0]); // old behavior.
                 // self.pc = nnn + u16::from(self.vr[x]); // new behavior.
             }
-            (0xC, _, _, _) => self.vr[x] = fastrand::u8(..) & nn,
-            (0xD, _, _, _) => {
-                // let mut sprite = Vec::<Vec<u8>>::new();
-                // for h in 0..n {
-                //     let mut row = Vec::<u8>::new();
-                //     let mut byte = 
-                // }
-                let sprite = &self.memory[self.ir as usize .. (self.ir + n) as usize];
-                self.vr[0xF] = self.display.draw(sprite, self.vr[x], self.vr[y]);
+            Instruction::Random(x, nn) => self.vr[x as usize] = self.rng.next_u8() & nn,
+            Instruction::Draw(x, y, n) => {
+                let mut sprite = [0u8; 15];
+                let sprite = &mut sprite[..n as usize];
+                self.memory.read_into(self.ir as usize, sprite)?;
+                self.vr[0xF] = self.display.draw(sprite, self.vr[x as usize], self.vr[y as usize]);
+                if self.options.quirks().display_wait() {
+                    self.waiting_for_vblank = true;
+                }
             }
-            (0xE, _, 0x9, 0xE) => if self.keyboard[self.vr[x] as usize] {self.fetch();},
-            (0xE, _, 0xA, 0x1) => if !self.keyboard[self.vr[x] as usize] {self.fetch();},
-            (0xF, _, 0x0, 0x7) => self.vr[x] = self.delay_timer,
-            (0xF, _, 0x0, 0xA) => {
+            Instruction::SkipIfKey(x) if self.is_key_down(self.vr[x as usize])? => {self.fetch()?;},
+            Instruction::SkipIfNotKey(x) if !self.is_key_down(self.vr[x as usize])? => {self.fetch()?;},
+            Instruction::GetDelay(x) => self.vr[x as usize] = self.delay_timer,
+            Instruction::WaitForKey(x) => {
+                // Keep executing FX0A until a key is latched, timers carry on counting meanwhile.
                 self.pc = self.pc.wrapping_sub(2);
-                for key in 0..self.keyboard.len() {
-                    if self.keyboard[key] {
-                        self.vr[x] = key as u8;
-                        self.fetch();
-                        self.keyboard[key] = false;
-                        break;
-                    }
+                if let Some(key) = self.wait_for_key() {
+                    self.vr[x as usize] = key.index() as u8;
+                    self.pc = self.pc.wrapping_add(2);
                 }
             }
-            (0xF, _, 0x1, 0x5) => self.delay_timer = self.vr[x],
-            (0xF, _, 0x1, 0x8) => self.sound_timer = self.vr[x],
-            (0xF, _, 0x1, 0xE) => {
+            Instruction::SetDelay(x) => self.delay_timer = self.vr[x as usize],
+            Instruction::SetSound(x) => self.sound_timer = self.vr[x as usize],
+            Instruction::AddIndex(x) => {
                 // Ambiguous instruction! TODO: option to switch implementations.
-                if self.ir < 0x0FFF && (self.ir + u16::from(self.vr[x])) >= 0x1000 { // new behavior.
+                if self.ir < 0x0FFF && (self.ir + u16::from(self.vr[x as usize])) >= 0x1000 { // new behavior.
                     self.vr[0xF] = 1
                 }
-                self.ir = self.ir.wrapping_add(u16::from(self.vr[x]));
+                self.ir = self.ir.wrapping_add(u16::from(self.vr[x as usize]));
+            }
+            Instruction::Font(x) => {
+                self.ir = self.options.font_address().wrapping_add(u16::from(self.vr[x as usize] & 0x0F) * Font::CHAR_SIZE);
+            }
+            Instruction::BigFont(x) => {
+                self.ir = self.options.big_font_address().wrapping_add(u16::from(self.vr[x as usize] & 0x0F) * BigFont::CHAR_SIZE);
             }
-            (0xF, _, 0x2, 0x9) => self.ir = 0x50 + u16::from((self.vr[x] & 0x0F) * 5),
-            (0xF, _, 0x3, 0x3) => {
-                self.memory[self.ir as usize] = self.vr[x] / 100;
-                self.memory[self.ir as usize + 1] = (self.vr[x] / 10) % 10;
-                self.memory[self.ir as usize + 2] = self.vr[x] % 10;
+            Instruction::Bcd(x) => {
+                let value = self.vr[x as usize];
+                self.write_memory(self.ir as usize, &[value / 100, (value / 10) % 10, value % 10])?;
             }
-            (0xF, _, 0x5, 0x5) => {
+            Instruction::Store(x) => {
                 // Ambiguous instruction! TODO: option to switch implementations.
-                for i in 0..=x { // new behavior.
-                    self.memory[self.ir as usize + i] = self.vr[i];
-                }
+                let registers = self.vr;
+                self.write_memory(self.ir as usize, &registers[..=x as usize])?; // new behavior.
             }
-            (0xF, _, 0x6, 0x5) => {
+            Instruction::Load(x) => {
                 // Ambiguous instruction! TODO: option to switch implementations.
-                for i in 0..=x { // new behavior.
-                    self.vr[i] = self.memory[self.ir as usize + i];
+                for i in 0..=x as usize { // new behavior.
+                    self.vr[i] = self.memory.read(self.ir as usize + i)?;
                 }
             }
             _ => {}
@@ -222,12 +320,123 @@ impl Chip8 {
         Ok(())
     }
 
-    pub fn execute() {
-        todo!();
+    // Every store the program makes goes through here so cached blocks decoded from
+    // the old bytes get thrown away.
+    fn write_memory(&mut self, address: usize, data: &[u8]) -> Result<(), Error> {
+        for (i, &byte) in data.iter().enumerate() {
+            self.memory.write(address + i, byte)?;
+            if self.options.engine() != Engine::Interpreter {
+                let physical = (address + i) % self.memory.size();
+                self.cache.invalidate(physical, physical + 1);
+            }
+        }
+        Ok(())
+    }
+
+    // Whether the key a ROM asked for is down, going by the key policy past F.
+    fn is_key_down(&mut self, value: u8) -> Result<bool, Error> {
+        let key = match Key::new(value) {
+            Some(key) => key,
+            None => match self.options.key_policy() {
+                KeyPolicy::Mask => Key::from_nibble(value),
+                KeyPolicy::Ignore => return Ok(false),
+                KeyPolicy::Error => return Err(Error::InvalidKey(value)),
+            },
+        };
+        Ok(self.keypad.poll(key))
     }
 
-    pub fn run_cpu_cycle(&mut self) -> Result<(), String> {
-        if self.last_tick.elapsed() >= Duration::from_micros(TIMER_RATE) {
+    fn wait_for_key(&mut self) -> Option<Key> {
+        self.keypad.poll_all();
+        if self.key_wait == KeyWaitState::Idle {
+            // Only presses from now on count, keys already held don't trigger.
+            self.key_wait_edges.clear_edges();
+            self.key_wait = KeyWaitState::Waiting;
+        }
+        if self.key_wait == KeyWaitState::Waiting {
+            let key = self.key_wait_edges.first_pressed()?;
+            match self.options.quirks().key_wait() {
+                KeyWait::Press => {
+                    self.key_wait = KeyWaitState::Idle;
+                    return Some(key);
+                }
+                // The COSMAC VIP waits for the key to be let go again.
+                KeyWait::Release => self.key_wait = KeyWaitState::Held(key),
+            }
+        }
+        match self.key_wait {
+            // Up again after the press, which may have been a tap within a single frame.
+            KeyWaitState::Held(key) if !self.keypad.is_down(key) => {
+                self.key_wait = KeyWaitState::Idle;
+                Some(key)
+            }
+            _ => None,
+        }
+    }
+
+    // Runs up to `budget` instructions, stopping early for the display wait quirk.
+    fn run_instructions(&mut self, budget: u16) -> Result<(), Error> {
+        let mut left = budget as usize;
+        let result = self.run_budget(&mut left);
+        self.instructions = self.instructions.saturating_add((budget as usize - left) as u64);
+        result
+    }
+
+    fn run_budget(&mut self, budget: &mut usize) -> Result<(), Error> {
+        while *budget > 0 && !self.waiting_for_vblank {
+            let block = match self.options.engine() {
+                Engine::Interpreter => None,
+                _ => self.cache.get(self.pc, &self.memory)?,
+            };
+            let Some(block) = block else {
+                let opcode = self.fetch()?;
+                self.decode(opcode)?;
+                *budget -= 1;
+                continue;
+            };
+            let compiled = self.run_compiled(&block, *budget);
+            *budget -= compiled;
+            for &instruction in block.instructions()[compiled..].iter().take(*budget) {
+                self.pc = self.pc.wrapping_add(2);
+                self.execute(instruction)?;
+                *budget -= 1;
+                if self.waiting_for_vblank {
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Runs the natively compiled start of the block if there is one and it fits in
+    // the budget. Returns how many instructions that covered.
+    #[cfg(all(feature = "jit", target_arch = "x86_64"))]
+    fn run_compiled(&mut self, block: &Block, budget: usize) -> usize {
+        if self.options.engine() != Engine::Jit {
+            return 0;
+        }
+        match block.compiled() {
+            Some(compiled) if compiled.len() <= budget => {
+                compiled.run(&mut self.vr, &mut self.ir);
+                self.pc = self.pc.wrapping_add(compiled.len() as u16 * 2);
+                compiled.len()
+            }
+            _ => 0,
+        }
+    }
+
+    #[cfg(not(all(feature = "jit", target_arch = "x86_64")))]
+    fn run_compiled(&mut self, _block: &Block, _budget: usize) -> usize {
+        0
+    }
+
+    // Runs a single instruction, ticking the timers when 1/60s has passed. `now` can come
+    // from any clock that only goes forwards, like the time since the board powered on.
+    pub fn run_cpu_cycle(&mut self, now: Duration) -> Result<(), Error> {
+        let due = self.last_tick.is_none_or(|last_tick| now.saturating_sub(last_tick) >= TIMER_RATE);
+        if due {
+            self.last_tick = Some(now);
+            self.waiting_for_vblank = false;
             if self.delay_timer > 0 {
                 self.delay_timer -= 1;
             }
@@ -236,12 +445,14 @@ impl Chip8 {
             }
         }
 
-        let opcode = self.fetch();
-        self.decode(opcode)?;
-        Ok(())
+        if self.waiting_for_vblank {
+            return Ok(());
+        }
+
+        self.run_instructions(1)
     }
 
-    pub fn run_cpu_cycle_alternate(&mut self, tickrate: u16) -> Result<(), String> {
+    pub fn run_cpu_cycle_alternate(&mut self, tickrate: u16) -> Result<(), Error> {
         if self.delay_timer > 0 {
             self.delay_timer -= 1;
         }
@@ -249,11 +460,192 @@ impl Chip8 {
             self.sound_timer -= 1;
         }
 
-        for _ in 0..tickrate {
-            let opcode = self.fetch();
-            self.decode(opcode)?;
+        self.waiting_for_vblank = false;
+        // A draw with the display wait quirk uses up the rest of this frame.
+        self.run_instructions(tickrate)
+    }
+}
+
+// Fonts live below the program, past 0x200 a ROM would overwrite them (or they'd overwrite it).
+fn load_font(memory: &mut Memory, address: u16, data: &[u8]) -> Result<(), Error> {
+    let address = address as usize;
+    if address + data.len() > 0x200 {
+        return Err(Error::FontOverlapsProgram { address, len: data.len() });
+    }
+    memory.load(address, data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wait_for_key(chip8: &mut Chip8) {
+        chip8.pc = 0x202;
+        chip8.decode(0xF30A).unwrap();
+    }
+
+    #[test]
+    fn stack_depth_is_capped() {
+        let mut options = Options::new();
+        options.set_stack_depth(Some(Stack::MAX_DEPTH));
+        assert!(Chip8::new(options).is_ok());
+        let mut options = Options::new();
+        options.set_stack_depth(Some(99_999_999_999));
+        assert_eq!(Chip8::new(options).err(), Some(Error::StackTooDeep(99_999_999_999)));
+    }
+
+    #[test]
+    fn fonts_have_to_fit_below_the_program() {
+        let mut options = Options::new();
+        options.set_memory_size(Memory::CLASSIC);
+        options.set_font_address(0xFFE);
+        assert_eq!(Chip8::new(options).err(), Some(Error::FontOverlapsProgram { address: 0xFFE, len: Font::SIZE }));
+        let mut options = Options::new();
+        options.set_big_font_address(0x1F0);
+        assert!(matches!(Chip8::new(options), Err(Error::FontOverlapsProgram { address: 0x1F0, .. })));
+        // Right up against the program is fine.
+        let mut options = Options::new();
+        options.set_font_address(0x200 - Font::SIZE as u16);
+        assert!(Chip8::new(options).is_ok());
+    }
+
+    #[test]
+    fn fonts_cant_overlap() {
+        let mut options = Options::new();
+        options.set_font_address(0x9C);
+        assert_eq!(Chip8::new(options).err(), Some(Error::FontsOverlap { font: 0x9C, big_font: 0xA0 }));
+        let mut options = Options::new();
+        options.set_big_font_address(0x10);
+        assert!(matches!(Chip8::new(options), Err(Error::FontsOverlap { .. })));
+        // Back to back is fine.
+        let mut options = Options::new();
+        options.set_font_address(0xA0 - Font::SIZE as u16);
+        assert!(Chip8::new(options).is_ok());
+    }
+
+    #[test]
+    fn key_wait_finishes_on_release() {
+        let mut chip8 = Chip8::new(Options::new()).unwrap();
+        wait_for_key(&mut chip8);
+        assert_eq!(chip8.pc, 0x200);
+        chip8.set_key(Key::K7, true);
+        wait_for_key(&mut chip8);
+        assert_eq!(chip8.pc, 0x200);
+        chip8.set_key(Key::K7, false);
+        wait_for_key(&mut chip8);
+        assert_eq!(chip8.pc, 0x202);
+        assert_eq!(chip8.vr[3], 0x7);
+        // The real key state is left alone.
+        chip8.set_key(Key::K7, true);
+        assert!(chip8.keypad.is_down(Key::K7));
+    }
+
+    #[test]
+    fn key_wait_ignores_keys_held_before_waiting() {
+        for key_wait in [KeyWait::Release, KeyWait::Press] {
+            let mut options = Options::new();
+            options.quirks_mut().set_key_wait(key_wait);
+            let mut chip8 = Chip8::new(options).unwrap();
+            chip8.set_key(Key::K1, true);
+            wait_for_key(&mut chip8);
+            chip8.set_key(Key::K1, false);
+            wait_for_key(&mut chip8);
+            assert_eq!(chip8.pc, 0x200, "{:?}", key_wait);
+        }
+    }
+
+    #[test]
+    fn key_wait_press_does_not_repeat_while_held() {
+        let mut options = Options::new();
+        options.quirks_mut().set_key_wait(KeyWait::Press);
+        let mut chip8 = Chip8::new(options).unwrap();
+        wait_for_key(&mut chip8);
+        chip8.set_key(Key::KA, true);
+        wait_for_key(&mut chip8);
+        assert_eq!(chip8.pc, 0x202);
+        assert_eq!(chip8.vr[3], 0xA);
+        wait_for_key(&mut chip8);
+        wait_for_key(&mut chip8);
+        assert_eq!(chip8.pc, 0x200);
+    }
+
+    #[test]
+    fn key_wait_catches_a_tap_within_one_frame() {
+        let mut chip8 = Chip8::new(Options::new()).unwrap();
+        wait_for_key(&mut chip8);
+        chip8.set_key(Key::K2, true);
+        chip8.set_key(Key::K2, false);
+        wait_for_key(&mut chip8);
+        assert_eq!(chip8.pc, 0x202);
+        assert_eq!(chip8.vr[3], 0x2);
+    }
+
+    #[test]
+    fn font_instructions_point_into_the_active_font() {
+        let mut options = Options::new();
+        options.set_font(Font::CosmacVip);
+        options.set_font_address(0x100);
+        options.set_big_font_address(0x180);
+        let mut chip8 = Chip8::new(options).unwrap();
+        chip8.vr[2] = 0x1;
+        chip8.decode(0xF229).unwrap();
+        assert_eq!(chip8.ir, 0x105);
+        assert_eq!(chip8.memory.read(0x105).unwrap(), Font::CosmacVip.data()[5]);
+        chip8.decode(0xF230).unwrap();
+        assert_eq!(chip8.ir, 0x18A);
+        assert_eq!(chip8.memory.read(0x18A).unwrap(), BigFont::Schip.data()[10]);
+    }
+
+    #[test]
+    fn timers_count_while_waiting_for_a_key() {
+        let mut chip8 = Chip8::new(Options::new()).unwrap();
+        chip8.load_rom(vec![0xF3, 0x0A]).unwrap();
+        chip8.sound_timer = 10;
+        for _ in 0..5 {
+            chip8.run_cpu_cycle_alternate(11).unwrap();
         }
+        assert_eq!(chip8.sound_timer, 5);
+        assert_eq!(chip8.pc, 0x200);
+    }
 
-        Ok(())
+    #[test]
+    fn timers_tick_on_the_injected_clock() {
+        let mut chip8 = Chip8::new(Options::new()).unwrap();
+        // Jump to self.
+        chip8.load_rom(vec![0x12, 0x00]).unwrap();
+        chip8.delay_timer = 10;
+        let ms = Duration::from_millis;
+        chip8.run_cpu_cycle(ms(1000)).unwrap();
+        assert_eq!(chip8.delay_timer, 9);
+        chip8.run_cpu_cycle(ms(1010)).unwrap();
+        assert_eq!(chip8.delay_timer, 9);
+        chip8.run_cpu_cycle(ms(1017)).unwrap();
+        assert_eq!(chip8.delay_timer, 8);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn seeded_random_numbers_repeat() {
+        let mut options = Options::new();
+        options.set_seed(Some(7));
+        let mut a = Chip8::new(options).unwrap();
+        let mut options = Options::new();
+        options.set_seed(Some(7));
+        let mut b = Chip8::new(options).unwrap();
+        for _ in 0..16 {
+            a.decode(0xC0FF).unwrap();
+            b.decode(0xC0FF).unwrap();
+            assert_eq!(a.vr[0], b.vr[0]);
+        }
+    }
+
+    #[test]
+    fn errors_are_reported_with_the_address() {
+        let mut options = Options::new();
+        options.set_memory_size(Memory::CLASSIC);
+        options.set_memory_policy(MemoryPolicy::Error);
+        let mut chip8 = Chip8::new(options).unwrap();
+        chip8.pc = 0x0FFF;
+        assert_eq!(chip8.fetch(), Err(Error::MemoryOutOfBounds(0x1000)));
+        assert_eq!(String::from(Error::MemoryOutOfBounds(0x1000)), "Memory access out of bounds at 0x1000.");
+    }
+}
@@ -1,25 +1,292 @@
+// Only the window and terminal frontends make sound.
+#[cfg(all(feature = "audio", any(feature = "window", feature = "tui")))]
+mod audio;
+mod benchmark;
+mod config;
+#[cfg(feature = "gamepad")]
+mod controllers;
+mod headless;
+#[cfg(feature = "tui")]
+mod tui;
+#[cfg(feature = "window")]
 mod window;
 
-use chip_egg::Chip8;
+use chip_egg::{Chip8, Recorder};
 use chip_egg::Options;
-use window::run;
+use chip_egg::{BigFont, CrtEffects, Engine, Font, KeyPolicy, KeyWait, Memory, MemoryPolicy, OverlayMode, Palette, Persistence, Quirks, SpriteEdge, Stack, StackPolicy};
+use config::Config;
 
 use std::env;
-use std::fs::read;
+use std::fs::{read, File};
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::process;
 
 // const CPU_CLOCK: u64 = 1428; // 700Hz
 // const CPU_CLOCK: u64 = 1000; // 1000Hz
 // const CPU_CLOCK: u64 = 833; // 1200Hz
+#[cfg(feature = "window")]
 const TIMER_RATE: u64 = 16666; //60Hz
 const TICK_RATE: u16 = 700/60;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
-    let filename = args[1].to_owned();
+    let mut options = Options::new();
+    let mut config = Config::load();
+    // Whether a flag changed something that should stick for the next run.
+    let mut save_config = false;
+    let mut filename = None;
+    let mut headless_frames = None;
+    let mut benchmark = None;
+    let mut use_tui = false;
+    let mut screenshot = None;
+    let mut record = None;
+    let mut load_state = None;
+    let mut save_state = None;
+    let mut disassemble = false;
+    let mut assemble = None;
+    let mut args = args[1..].iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--quirks" => {
+                let name = args.next().expect("--quirks needs a profile name.");
+                let quirks = Quirks::from_name(name).expect("Unknown quirk profile.");
+                options.set_quirks(quirks);
+            }
+            "--display-wait" => options.quirks_mut().set_display_wait(true),
+            "--wrap" => options.quirks_mut().set_sprite_edge(SpriteEdge::Wrap),
+            "--clip" => options.quirks_mut().set_sprite_edge(SpriteEdge::Clip),
+            "--key-press" => options.quirks_mut().set_key_wait(KeyWait::Press),
+            "--font" => {
+                let font = args.next().expect("--font needs a font name or file.");
+                let font = Font::from_name(font)
+                    .unwrap_or_else(|| Font::load(font).expect("Error loading font."));
+                options.set_font(font);
+            }
+            "--font-address" => {
+                let address = args.next().expect("--font-address needs an address.");
+                options.set_font_address(parse_address(address));
+            }
+            "--big-font" => {
+                let font = args.next().expect("--big-font needs a font name or file.");
+                let font = BigFont::from_name(font)
+                    .unwrap_or_else(|| BigFont::load(font).expect("Error loading big font."));
+                options.set_big_font(font);
+            }
+            "--big-font-address" => {
+                let address = args.next().expect("--big-font-address needs an address.");
+                options.set_big_font_address(parse_address(address));
+            }
+            "--palette" => {
+                let palette = args.next().expect("--palette needs a palette name or hex colours.");
+                let palette = Palette::parse(palette).expect("Invalid palette.");
+                config.set("palette", palette.name());
+                save_config = true;
+            }
+            "--persistence" => {
+                let persistence = args.next().expect("--persistence needs off, blend:N or decay:RATE.");
+                let persistence = Persistence::parse(persistence).expect("Invalid persistence.");
+                config.set("persistence", &persistence.to_string());
+                save_config = true;
+            }
+            "--crt" => {
+                let effects = args.next().expect("--crt needs a list of effects.");
+                let effects = CrtEffects::parse(effects).expect("Invalid CRT effects.");
+                config.set("crt", &effects.to_string());
+                save_config = true;
+            }
+            "--keypad" => {
+                let mode = args.next().expect("--keypad needs off, side or over.");
+                let mode = OverlayMode::parse(mode).expect("Invalid keypad overlay.");
+                config.set("keypad", &mode.to_string());
+                save_config = true;
+            }
+            "--fast-forward" => {
+                let rate: u16 = args.next().and_then(|rate| rate.parse().ok()).expect("--fast-forward needs a number.");
+                config.set("fast_forward", &rate.to_string());
+                save_config = true;
+            }
+            "--scale" => {
+                let scale: usize = args.next().and_then(|scale| scale.parse().ok()).expect("--scale needs a number.");
+                config.set_scale(scale);
+            }
+            "--headless" => {
+                let frames = args.next().and_then(|frames| frames.parse().ok()).expect("--headless needs a number of frames.");
+                headless_frames = Some(frames);
+            }
+            "--engine" => {
+                let engine = args.next().expect("--engine needs interpreter, cached or jit.");
+                options.set_engine(Engine::from_name(engine).expect("Unknown engine."));
+            }
+            "--tui" => use_tui = true,
+            "--load-state" => {
+                let path = args.next().expect("--load-state needs a file name.");
+                load_state = Some(PathBuf::from(path));
+            }
+            "--save-state" => {
+                let path = args.next().expect("--save-state needs a file name.");
+                save_state = Some(PathBuf::from(path));
+            }
+            "--disassemble" => disassemble = true,
+            "--assemble" => {
+                let path = args.next().expect("--assemble needs a file name for the ROM.");
+                assemble = Some(PathBuf::from(path));
+            }
+            "--seed" => {
+                let seed = args.next().and_then(|seed| seed.parse().ok()).expect("--seed needs a number.");
+                options.set_seed(Some(seed));
+            }
+            "--benchmark" => {
+                let instructions = args.next().and_then(|count| count.parse().ok()).expect("--benchmark needs a number of instructions.");
+                benchmark = Some(instructions);
+            }
+            "--screenshot" => {
+                let path = args.next().expect("--screenshot needs a file name.");
+                screenshot = Some(PathBuf::from(path));
+            }
+            "--record" => {
+                let path = args.next().expect("--record needs a file name.");
+                record = Some((path.to_owned(), false));
+            }
+            "--record-raw" => {
+                let path = args.next().expect("--record-raw needs a file name, or - for stdout.");
+                record = Some((path.to_owned(), true));
+            }
+            "--memory" => match args.next().map(String::as_str) {
+                Some("4k") => options.set_memory_size(Memory::CLASSIC),
+                Some("64k") => options.set_memory_size(Memory::XO_CHIP),
+                _ => panic!("--memory needs to be 4k or 64k."),
+            },
+            "--memory-policy" => match args.next().map(String::as_str) {
+                Some("wrap") => options.set_memory_policy(MemoryPolicy::Wrap),
+                Some("error") => options.set_memory_policy(MemoryPolicy::Error),
+                _ => panic!("--memory-policy needs to be wrap or error."),
+            },
+            "--stack-depth" => match args.next().map(String::as_str) {
+                Some("vip") => options.set_stack_depth(Some(Stack::VIP_DEPTH)),
+                Some("schip") => options.set_stack_depth(Some(Stack::SCHIP_DEPTH)),
+                Some("unlimited") => options.set_stack_depth(None),
+                Some(depth) => options.set_stack_depth(Some(depth.parse().expect("Invalid stack depth."))),
+                None => panic!("--stack-depth needs a depth."),
+            },
+            "--stack-policy" => match args.next().map(String::as_str) {
+                Some("error") => options.set_stack_policy(StackPolicy::Error),
+                Some("wrap") => options.set_stack_policy(StackPolicy::Wrap),
+                Some("ignore") => options.set_stack_policy(StackPolicy::Ignore),
+                _ => panic!("--stack-policy needs to be error, wrap or ignore."),
+            },
+            "--key-policy" => match args.next().map(String::as_str) {
+                Some("mask") => options.set_key_policy(KeyPolicy::Mask),
+                Some("ignore") => options.set_key_policy(KeyPolicy::Ignore),
+                Some("error") => options.set_key_policy(KeyPolicy::Error),
+                _ => panic!("--key-policy needs to be mask, ignore or error."),
+            },
+            _ => filename = Some(arg.to_owned()),
+        }
+    }
+    let filename = filename.expect("No ROM file given.");
+    if save_config {
+        config.save();
+    }
 
     // let file = File::open(filename).expect("Error opening file.");
     // let mut buf = [0u8; 3584];
-    let rom = read(filename).expect("Error reading file.");
+    let rom = read(&filename).expect("Error reading file.");
+    if disassemble {
+        print!("{}", chip_egg::disassemble(&rom, 0x200));
+        return;
+    }
+    if let Some(path) = assemble {
+        let source = String::from_utf8_lossy(&rom);
+        let assembled = chip_egg::assemble(&source).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(1);
+        });
+        std::fs::write(path, assembled).expect("Error writing ROM.");
+        return;
+    }
 
-    pollster::block_on(run(rom)).expect("Pollster Error");
-}
\ No newline at end of file
+    let mut chip8 = match Chip8::new(options) {
+        Ok(chip8) => chip8,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
+    if let Err(e) = chip8.load_rom(rom) {
+        eprintln!("Error loading ROM: {}", e);
+        process::exit(1);
+    }
+    if let Some(path) = load_state {
+        let state = read(path).expect("Error reading save state.");
+        if let Err(e) = chip8.load_state(&state) {
+            eprintln!("Error loading save state: {}", e);
+            process::exit(1);
+        }
+    }
+
+    if let Some(instructions) = benchmark {
+        if let Err(e) = benchmark::run(chip8, instructions) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    let recorder = record.map(|(path, raw)| {
+        let writer: Box<dyn Write> = if path == "-" {
+            Box::new(BufWriter::new(io::stdout()))
+        } else {
+            Box::new(BufWriter::new(File::create(&path).expect("Error creating recording file.")))
+        };
+        if raw {
+            Recorder::raw(writer, &config.palette(), config.scale())
+        } else {
+            Recorder::gif(writer, &config.palette(), config.scale(), chip8.display()).expect("Error starting recording.")
+        }
+    });
+
+    if let Some(frames) = headless_frames {
+        if let Err(e) = headless::run(chip8, &config, frames, screenshot, recorder, save_state) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if use_tui {
+        run_tui(chip8, &config);
+        return;
+    }
+
+    run_window(chip8, config, recorder, &filename);
+}
+
+#[cfg(feature = "tui")]
+fn run_tui(chip8: Chip8, config: &Config) {
+    if let Err(e) = tui::run(chip8, config) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+#[cfg(not(feature = "tui"))]
+fn run_tui(_chip8: Chip8, _config: &Config) {
+    eprintln!("Built without the tui feature.");
+    process::exit(1);
+}
+
+#[cfg(feature = "window")]
+fn run_window(chip8: Chip8, config: Config, recorder: Option<Recorder<Box<dyn Write>>>, rom: &str) {
+    pollster::block_on(window::run(chip8, config, recorder, rom)).expect("Pollster Error");
+}
+
+#[cfg(not(feature = "window"))]
+fn run_window(_chip8: Chip8, _config: Config, _recorder: Option<Recorder<Box<dyn Write>>>, _rom: &str) {
+    eprintln!("Built without the window feature, use --headless.");
+    process::exit(1);
+}
+fn parse_address(address: &str) -> u16 {
+    let hex = address.trim_start_matches("0x");
+    u16::from_str_radix(hex, 16).expect("Addresses are in hex, like 0x50.")
+}
@@ -0,0 +1,123 @@
+// All interpreter memory access goes through here so a bad ROM can't index
+// outside the buffer. What happens past the end depends on the policy.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::Error;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MemoryPolicy {
+    // Addresses past the end wrap back around to 0.
+    Wrap,
+    // Addresses past the end are an error and stop the interpreter.
+    Error,
+}
+
+pub struct Memory {
+    data: Vec<u8>,
+    policy: MemoryPolicy,
+}
+
+impl Memory {
+    pub const CLASSIC: usize = 4096;
+    pub const XO_CHIP: usize = 65536;
+    // Programs start at 0x200, so anything smaller can't even hold a ROM.
+    const MIN_SIZE: usize = 0x200;
+
+    pub fn new(size: usize, policy: MemoryPolicy) -> Memory {
+        // I and PC are 16 bits, anything above 64K can never be addressed.
+        let size = size.clamp(Self::MIN_SIZE, Self::XO_CHIP);
+        Memory {
+            data: vec![0; size],
+            policy,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn policy(&self) -> MemoryPolicy {
+        self.policy
+    }
+
+    fn address(&self, address: usize) -> Result<usize, Error> {
+        if address < self.data.len() {
+            return Ok(address);
+        }
+        match self.policy {
+            MemoryPolicy::Wrap => Ok(address % self.data.len()),
+            MemoryPolicy::Error => Err(Error::MemoryOutOfBounds(address)),
+        }
+    }
+
+    pub fn read(&self, address: usize) -> Result<u8, Error> {
+        Ok(self.data[self.address(address)?])
+    }
+
+    pub fn write(&mut self, address: usize, value: u8) -> Result<(), Error> {
+        let address = self.address(address)?;
+        self.data[address] = value;
+        Ok(())
+    }
+
+    pub fn read_into(&self, address: usize, buf: &mut [u8]) -> Result<(), Error> {
+        for (i, byte) in buf.iter_mut().enumerate() {
+            *byte = self.read(address + i)?;
+        }
+        Ok(())
+    }
+
+    // Loading never wraps, data that doesn't fit is always an error.
+    pub fn load(&mut self, address: usize, data: &[u8]) -> Result<(), Error> {
+        let end = address + data.len();
+        if end > self.data.len() {
+            return Err(Error::LoadTooLarge { len: data.len(), address, size: self.data.len() });
+        }
+        self.data[address..end].copy_from_slice(data);
+        Ok(())
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_past_the_end() {
+        let mut memory = Memory::new(Memory::CLASSIC, MemoryPolicy::Wrap);
+        memory.write(0x1000, 0xAB).unwrap();
+        assert_eq!(memory.read(0x0000).unwrap(), 0xAB);
+        let mut buf = [0; 2];
+        memory.read_into(0x0FFF, &mut buf).unwrap();
+        assert_eq!(buf, [0x00, 0xAB]);
+    }
+
+    #[test]
+    fn errors_past_the_end() {
+        let mut memory = Memory::new(Memory::CLASSIC, MemoryPolicy::Error);
+        assert!(memory.write(0x1000, 0xAB).is_err());
+        assert!(memory.read(0x1000).is_err());
+        assert!(memory.read_into(0x0FFF, &mut [0; 2]).is_err());
+        assert!(memory.read(0x0FFF).is_ok());
+    }
+
+    #[test]
+    fn load_rejects_data_that_doesnt_fit() {
+        let mut memory = Memory::new(Memory::CLASSIC, MemoryPolicy::Wrap);
+        assert!(memory.load(0x200, &[0; 0xE00]).is_ok());
+        assert!(memory.load(0x200, &[0; 0xE01]).is_err());
+    }
+
+    #[test]
+    fn size_is_clamped() {
+        assert_eq!(Memory::new(0, MemoryPolicy::Wrap).size(), 0x200);
+        assert_eq!(Memory::new(1 << 20, MemoryPolicy::Wrap).size(), Memory::XO_CHIP);
+    }
+}
@@ -0,0 +1,405 @@
+// Every opcode through `fetch` and `decode`, with a tiny builder for machine states:
+//
+//     let chip8 = Machine::new().v(0, 0xFF).v(1, 0x01).run(&[0x8014]);
+//     assert_eq!((chip8.vr[0], chip8.vr[0xF]), (0x00, 1));
+
+use alloc::vec::Vec;
+
+use crate::{Chip8, Error, Font, Key, KeyPolicy, KeyWait, Memory, MemoryPolicy, Options, Quirks, SpriteEdge, StackPolicy};
+
+struct Machine(Chip8);
+
+impl Machine {
+    fn new() -> Machine {
+        Machine::with(Options::new())
+    }
+
+    fn with(mut options: Options) -> Machine {
+        options.set_seed(Some(1));
+        Machine(Chip8::new(options).unwrap())
+    }
+
+    fn quirks(quirks: Quirks) -> Machine {
+        let mut options = Options::new();
+        options.set_quirks(quirks);
+        Machine::with(options)
+    }
+
+    fn v(mut self, x: usize, value: u8) -> Machine {
+        self.0.vr[x] = value;
+        self
+    }
+
+    fn i(mut self, ir: u16) -> Machine {
+        self.0.ir = ir;
+        self
+    }
+
+    fn memory(mut self, address: usize, bytes: &[u8]) -> Machine {
+        self.0.memory.load(address, bytes).unwrap();
+        self
+    }
+
+    fn key(mut self, key: Key) -> Machine {
+        self.0.set_key(key, true);
+        self
+    }
+
+    fn delay(mut self, value: u8) -> Machine {
+        self.0.delay_timer = value;
+        self
+    }
+
+    fn call(mut self, address: u16) -> Machine {
+        self.0.stack.push(address).unwrap();
+        self
+    }
+
+    // Loads the opcodes at PC and runs them until PC leaves them, so skips, jumps and
+    // calls stop the run where they land.
+    fn try_run(mut self, opcodes: &[u16]) -> Result<Chip8, Error> {
+        let start = self.0.pc;
+        let bytes: Vec<u8> = opcodes.iter().flat_map(|opcode| opcode.to_be_bytes()).collect();
+        self.0.memory.load(start as usize, &bytes)?;
+        let end = start + bytes.len() as u16;
+        for _ in 0..100 {
+            if !(start..end).contains(&self.0.pc) {
+                break;
+            }
+            let opcode = self.0.fetch()?;
+            self.0.decode(opcode)?;
+        }
+        Ok(self.0)
+    }
+
+    fn run(self, opcodes: &[u16]) -> Chip8 {
+        self.try_run(opcodes).unwrap()
+    }
+}
+
+// Every combination of the quirk settings.
+fn all_quirks() -> Vec<Quirks> {
+    let mut all = Vec::new();
+    for display_wait in [false, true] {
+        for sprite_edge in [SpriteEdge::Clip, SpriteEdge::Wrap] {
+            for key_wait in [KeyWait::Release, KeyWait::Press] {
+                let mut quirks = Quirks::new();
+                quirks.set_display_wait(display_wait);
+                quirks.set_sprite_edge(sprite_edge);
+                quirks.set_key_wait(key_wait);
+                all.push(quirks);
+            }
+        }
+    }
+    all
+}
+
+// (VX, VY, opcode, result, VF) with X = 0 and Y = 1. None for VF means it's left alone.
+const ALU: &[(u8, u8, u16, u8, Option<u8>)] = &[
+    (0x12, 0x34, 0x6056, 0x56, None),
+    (0xFF, 0x00, 0x7002, 0x01, None),
+    (0x12, 0x34, 0x8010, 0x34, None),
+    (0x0C, 0x0A, 0x8011, 0x0E, None),
+    (0x0C, 0x0A, 0x8012, 0x08, None),
+    (0x0C, 0x0A, 0x8013, 0x06, None),
+    (0x10, 0x20, 0x8014, 0x30, Some(0)),
+    (0xFF, 0x01, 0x8014, 0x00, Some(1)),
+    (0x80, 0x80, 0x8014, 0x00, Some(1)),
+    (0x05, 0x03, 0x8015, 0x02, Some(1)),
+    (0x05, 0x05, 0x8015, 0x00, Some(1)),
+    (0x03, 0x05, 0x8015, 0xFE, Some(0)),
+    (0x05, 0x00, 0x8016, 0x02, Some(1)),
+    (0x04, 0x00, 0x8016, 0x02, Some(0)),
+    (0x03, 0x05, 0x8017, 0x02, Some(1)),
+    (0x05, 0x05, 0x8017, 0x00, Some(1)),
+    (0x05, 0x03, 0x8017, 0xFE, Some(0)),
+    (0x81, 0x00, 0x801E, 0x02, Some(1)),
+    (0x41, 0x00, 0x801E, 0x82, Some(0)),
+];
+
+#[test]
+fn arithmetic_is_the_same_under_every_quirk() {
+    for quirks in all_quirks() {
+        for &(vx, vy, opcode, result, flag) in ALU {
+            // VF starts out as something no opcode sets it to.
+            let chip8 = Machine::quirks(quirks).v(0, vx).v(1, vy).v(0xF, 0xAA).run(&[opcode]);
+            let context = format!("{:04X} with {:02X}, {:02X} under {:?}", opcode, vx, vy, quirks);
+            assert_eq!(chip8.vr[0], result, "{}", context);
+            assert_eq!(chip8.vr[0xF], flag.unwrap_or(0xAA), "{}", context);
+            assert_eq!(chip8.vr[1], vy, "{}", context);
+        }
+    }
+}
+
+#[test]
+fn flag_wins_when_x_is_f() {
+    // (VF, VY, opcode, VF afterwards)
+    let cases = [
+        (0xFF, 0x01, 0x8F14, 1),
+        (0x10, 0x20, 0x8F14, 0),
+        (0x05, 0x03, 0x8F15, 1),
+        (0x03, 0x05, 0x8F15, 0),
+        (0x05, 0x00, 0x8F16, 1),
+        (0x04, 0x00, 0x8F16, 0),
+        (0x03, 0x05, 0x8F17, 1),
+        (0x05, 0x03, 0x8F17, 0),
+        (0x81, 0x00, 0x8F1E, 1),
+        (0x41, 0x00, 0x8F1E, 0),
+    ];
+    for (vf, vy, opcode, flag) in cases {
+        let chip8 = Machine::new().v(0xF, vf).v(1, vy).run(&[opcode]);
+        assert_eq!(chip8.vr[0xF], flag, "{:04X} with {:02X}, {:02X}", opcode, vf, vy);
+    }
+}
+
+#[test]
+fn vf_as_y_is_read_before_the_flag_is_written() {
+    let chip8 = Machine::new().v(0, 0x01).v(0xF, 0xFF).run(&[0x80F4]);
+    assert_eq!((chip8.vr[0], chip8.vr[0xF]), (0x00, 1));
+    let chip8 = Machine::new().v(0, 0x01).v(0xF, 0x02).run(&[0x80F5]);
+    assert_eq!((chip8.vr[0], chip8.vr[0xF]), (0xFF, 0));
+    let chip8 = Machine::new().v(0, 0x01).v(0xF, 0x03).run(&[0x80F7]);
+    assert_eq!((chip8.vr[0], chip8.vr[0xF]), (0x02, 1));
+}
+
+#[test]
+fn skips_move_past_the_next_instruction() {
+    // (opcode, VA, VB, skips)
+    let cases = [
+        (0x3A42, 0x42, 0x00, true),
+        (0x3A42, 0x41, 0x00, false),
+        (0x4A42, 0x41, 0x00, true),
+        (0x4A42, 0x42, 0x00, false),
+        (0x5AB0, 0x07, 0x07, true),
+        (0x5AB0, 0x07, 0x08, false),
+        (0x9AB0, 0x07, 0x08, true),
+        (0x9AB0, 0x07, 0x07, false),
+    ];
+    for quirks in all_quirks() {
+        for (opcode, va, vb, skips) in cases {
+            // The second instruction would change V0 if it ran.
+            let chip8 = Machine::quirks(quirks).v(0xA, va).v(0xB, vb).run(&[opcode, 0x60FF]);
+            let context = format!("{:04X} with {:02X}, {:02X} under {:?}", opcode, va, vb, quirks);
+            assert_eq!(chip8.pc, 0x204, "{}", context);
+            assert_eq!(chip8.vr[0] == 0xFF, !skips, "{}", context);
+        }
+    }
+}
+
+#[test]
+fn unknown_opcodes_do_nothing() {
+    for opcode in [0x0000, 0x0123, 0x5AB1, 0x8AB8, 0x9AB1, 0xEA00, 0xFAFF] {
+        let chip8 = Machine::new().v(0xA, 1).v(0xB, 2).i(0x300).run(&[opcode]);
+        assert_eq!(chip8.pc, 0x202, "{:04X}", opcode);
+        assert_eq!(chip8.vr[0xA..=0xB], [1, 2], "{:04X}", opcode);
+        assert_eq!(chip8.ir, 0x300, "{:04X}", opcode);
+    }
+}
+
+#[test]
+fn jumps() {
+    let chip8 = Machine::new().run(&[0x1ABC]);
+    assert_eq!(chip8.pc, 0xABC);
+    let chip8 = Machine::new().v(0, 0x10).v(1, 0x20).run(&[0xB300]);
+    assert_eq!(chip8.pc, 0x310);
+}
+
+#[test]
+fn calls_push_the_return_address() {
+    let chip8 = Machine::new().run(&[0x6000, 0x2400]);
+    assert_eq!(chip8.pc, 0x400);
+    assert_eq!(chip8.stack.entries(), [0x204]);
+}
+
+#[test]
+fn returns_pop_the_latest_call() {
+    let chip8 = Machine::new().call(0x300).call(0x400).run(&[0x00EE]);
+    assert_eq!(chip8.pc, 0x400);
+    assert_eq!(chip8.stack.entries(), [0x300]);
+}
+
+#[test]
+fn stack_overflow_and_underflow_follow_the_policy() {
+    let machine = |policy: StackPolicy| {
+        let mut options = Options::new();
+        options.set_stack_depth(Some(2));
+        options.set_stack_policy(policy);
+        Machine::with(options)
+    };
+    let full = |policy| machine(policy).call(0x300).call(0x400);
+    assert_eq!(full(StackPolicy::Error).try_run(&[0x2500]).err(), Some(Error::StackOverflow(2)));
+    assert_eq!(machine(StackPolicy::Error).try_run(&[0x00EE]).err(), Some(Error::StackUnderflow));
+
+    let chip8 = full(StackPolicy::Ignore).run(&[0x2500]);
+    assert_eq!(chip8.pc, 0x500);
+    assert_eq!(chip8.stack.entries(), [0x300, 0x400]);
+    let chip8 = machine(StackPolicy::Ignore).run(&[0x00EE]);
+    assert_eq!(chip8.pc, 0x202);
+
+    let chip8 = full(StackPolicy::Wrap).run(&[0x2500]);
+    assert_eq!(chip8.stack.entries(), [0x400, 0x202]);
+}
+
+#[test]
+fn set_index_and_add_index() {
+    let chip8 = Machine::new().run(&[0xA123]);
+    assert_eq!(chip8.ir, 0x123);
+    let chip8 = Machine::new().v(3, 0x10).v(0xF, 0).i(0x123).run(&[0xF31E]);
+    assert_eq!((chip8.ir, chip8.vr[0xF]), (0x133, 0));
+    // Going past 0xFFF sets VF, like the Amiga interpreter Spacefight 2091 relies on.
+    let chip8 = Machine::new().v(3, 0x02).v(0xF, 0).i(0xFFE).run(&[0xF31E]);
+    assert_eq!((chip8.ir, chip8.vr[0xF]), (0x1000, 1));
+}
+
+#[test]
+fn random_is_masked() {
+    let chip8 = Machine::new().run(&[0xC000, 0xC10F, 0xC2F0]);
+    assert_eq!(chip8.vr[0], 0);
+    assert_eq!(chip8.vr[1] & 0xF0, 0);
+    assert_eq!(chip8.vr[2] & 0x0F, 0);
+}
+
+#[test]
+fn draw_sets_vf_on_collision() {
+    let chip8 = Machine::new().i(0x300).memory(0x300, &[0xFF]).run(&[0xD011]);
+    assert_eq!(chip8.vr[0xF], 0);
+    assert_eq!(chip8.display.pixel(7, 0), 1);
+    let chip8 = Machine::new().i(0x300).memory(0x300, &[0xFF]).run(&[0xD011, 0xD011]);
+    assert_eq!(chip8.vr[0xF], 1);
+    assert_eq!(chip8.display.lit_pixels().count(), 0);
+}
+
+#[test]
+fn draw_clips_or_wraps_at_the_edge() {
+    for quirks in all_quirks() {
+        let chip8 = Machine::quirks(quirks).v(0, 60).v(1, 31).i(0x300).memory(0x300, &[0xFF, 0xFF]).run(&[0xD012]);
+        let wrapped = chip8.display.pixel(0, 0) != 0;
+        assert_eq!(wrapped, quirks.sprite_edge() == SpriteEdge::Wrap, "{:?}", quirks);
+        assert_eq!(chip8.display.pixel(63, 31), 1, "{:?}", quirks);
+    }
+}
+
+#[test]
+fn draw_coordinates_wrap_before_clipping() {
+    // The starting position always wraps, only the pixels hanging off the edge get clipped.
+    let chip8 = Machine::new().v(0, 64 + 2).v(1, 32 + 3).i(0x300).memory(0x300, &[0x80]).run(&[0xD011]);
+    assert_eq!(chip8.display.pixel(2, 3), 1);
+}
+
+#[test]
+fn draw_waits_for_vblank_with_the_quirk() {
+    for quirks in all_quirks() {
+        let chip8 = Machine::quirks(quirks).i(0x300).run(&[0xD001]);
+        assert_eq!(chip8.waiting_for_vblank, quirks.display_wait(), "{:?}", quirks);
+    }
+}
+
+#[test]
+fn clear_blanks_the_display() {
+    let chip8 = Machine::new().i(0x300).memory(0x300, &[0xFF]).run(&[0xD001, 0x00E0]);
+    assert_eq!(chip8.display.lit_pixels().count(), 0);
+    assert!(chip8.display.redraw());
+}
+
+#[test]
+fn key_skips() {
+    // (opcode, key held, skips) with VA = A.
+    let cases = [(0xEA9E, Key::KA, true), (0xEA9E, Key::KB, false), (0xEAA1, Key::KB, true), (0xEAA1, Key::KA, false)];
+    for quirks in all_quirks() {
+        for (opcode, key, skips) in cases {
+            let chip8 = Machine::quirks(quirks).v(0xA, 0xA).key(key).run(&[opcode, 0x60FF]);
+            let context = format!("{:04X} with {} held under {:?}", opcode, key, quirks);
+            assert_eq!(chip8.pc, 0x204, "{}", context);
+            assert_eq!(chip8.vr[0] == 0xFF, !skips, "{}", context);
+        }
+    }
+}
+
+#[test]
+fn keys_past_f_follow_the_policy() {
+    let machine = |policy: KeyPolicy, opcode: u16| {
+        let mut options = Options::new();
+        options.set_key_policy(policy);
+        Machine::with(options).v(0xA, 0x1A).key(Key::KA).try_run(&[opcode, 0x60FF])
+    };
+    // Masked, 1A is A, which is down.
+    assert_eq!(machine(KeyPolicy::Mask, 0xEA9E).unwrap().vr[0], 0);
+    assert_eq!(machine(KeyPolicy::Mask, 0xEAA1).unwrap().vr[0], 0xFF);
+    // Ignored, 1A is never down.
+    assert_eq!(machine(KeyPolicy::Ignore, 0xEA9E).unwrap().vr[0], 0xFF);
+    assert_eq!(machine(KeyPolicy::Ignore, 0xEAA1).unwrap().vr[0], 0);
+    assert_eq!(machine(KeyPolicy::Error, 0xEA9E).err(), Some(Error::InvalidKey(0x1A)));
+    assert_eq!(machine(KeyPolicy::Error, 0xEAA1).err(), Some(Error::InvalidKey(0x1A)));
+}
+
+#[test]
+fn key_wait_stays_put_until_a_key() {
+    for quirks in all_quirks() {
+        // Without a key FX0A keeps running itself, so the run gives up after 100 goes.
+        let chip8 = Machine::quirks(quirks).run(&[0xF30A]);
+        assert_eq!(chip8.pc, 0x200, "{:?}", quirks);
+        assert_eq!(chip8.vr[3], 0, "{:?}", quirks);
+    }
+}
+
+#[test]
+fn timers() {
+    let mut chip8 = Machine::new().v(0, 3).v(1, 5).run(&[0xF015, 0xF118, 0xF207]);
+    assert_eq!((chip8.delay_timer, chip8.sound_timer, chip8.vr[2]), (3, 5, 3));
+    for expected in [2, 1, 0, 0] {
+        chip8.run_cpu_cycle_alternate(0).unwrap();
+        assert_eq!(chip8.delay_timer, expected);
+    }
+    assert_eq!(chip8.sound_timer, 1);
+    let chip8 = Machine::new().delay(0x42).run(&[0xF507]);
+    assert_eq!(chip8.vr[5], 0x42);
+}
+
+#[test]
+fn font_points_at_the_digit() {
+    let chip8 = Machine::new().v(4, 0xA).run(&[0xF429]);
+    assert_eq!(chip8.ir, 0x50 + 0xA * Font::CHAR_SIZE);
+    // Only the low nibble counts.
+    let chip8 = Machine::new().v(4, 0x1A).run(&[0xF429]);
+    assert_eq!(chip8.ir, 0x50 + 0xA * Font::CHAR_SIZE);
+}
+
+#[test]
+fn bcd() {
+    for (value, digits) in [(0, [0, 0, 0]), (7, [0, 0, 7]), (42, [0, 4, 2]), (100, [1, 0, 0]), (255, [2, 5, 5])] {
+        let chip8 = Machine::new().v(6, value).i(0x300).run(&[0xF633]);
+        assert_eq!(chip8.memory.as_slice()[0x300..0x303], digits, "{}", value);
+        assert_eq!(chip8.ir, 0x300);
+    }
+}
+
+#[test]
+fn store_and_load_registers() {
+    let mut machine = Machine::new().i(0x300);
+    for x in 0..16 {
+        machine = machine.v(x, x as u8 * 3);
+    }
+    let chip8 = machine.run(&[0xF755]);
+    assert_eq!(chip8.memory.as_slice()[0x300..0x309], [0, 3, 6, 9, 12, 15, 18, 21, 0]);
+    assert_eq!(chip8.ir, 0x300);
+
+    let chip8 = Machine::new().i(0x300).memory(0x300, &[9, 8, 7, 6]).run(&[0xF265]);
+    assert_eq!(chip8.vr[..4], [9, 8, 7, 0]);
+    assert_eq!(chip8.ir, 0x300);
+}
+
+#[test]
+fn memory_ops_at_the_end_of_memory_follow_the_policy() {
+    let machine = |policy: MemoryPolicy| {
+        let mut options = Options::new();
+        options.set_memory_size(Memory::CLASSIC);
+        options.set_memory_policy(policy);
+        Machine::with(options).v(0, 255).i(0xFFE)
+    };
+    let chip8 = machine(MemoryPolicy::Wrap).run(&[0xF033]);
+    assert_eq!(chip8.memory.as_slice()[0xFFE..], [2, 5]);
+    assert_eq!(chip8.memory.as_slice()[0], 5);
+
+    let result = machine(MemoryPolicy::Error).try_run(&[0xF033]);
+    assert_eq!(result.err(), Some(Error::MemoryOutOfBounds(0x1000)));
+}
@@ -1,32 +1,144 @@
+use crate::{BigFont, Engine, Font, KeyPolicy, Memory, MemoryPolicy, Quirks, Stack, StackPolicy};
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Options {
-    font: [u8; 80]
+    font: Font,
+    font_address: u16,
+    big_font: BigFont,
+    big_font_address: u16,
+    quirks: Quirks,
+    memory_size: usize,
+    memory_policy: MemoryPolicy,
+    stack_depth: Option<usize>,
+    stack_policy: StackPolicy,
+    key_policy: KeyPolicy,
+    // Seed for CXNN. None picks a new one every run, or a fixed one without std.
+    seed: Option<u64>,
+    engine: Engine,
 }
 
 impl Options {
     pub fn new() -> Options {
         Options {
-            font: [
-                0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
-                0x20, 0x60, 0x20, 0x20, 0x70, // 1
-                0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
-                0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
-                0x90, 0x90, 0xF0, 0x10, 0x10, // 4
-                0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
-                0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
-                0xF0, 0x10, 0x20, 0x40, 0x40, // 7
-                0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
-                0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
-                0xF0, 0x90, 0xF0, 0x90, 0x90, // A
-                0xE0, 0x90, 0xE0, 0x90, 0xE0, // B
-                0xF0, 0x80, 0x80, 0x80, 0xF0, // C
-                0xE0, 0x90, 0x90, 0x90, 0xE0, // D
-                0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
-                0xF0, 0x80, 0xF0, 0x80, 0x80  // F
-            ]
+            font: Font::Octo,
+            font_address: 0x50,
+            big_font: BigFont::Schip,
+            big_font_address: 0xA0,
+            quirks: Quirks::new(),
+            memory_size: Memory::XO_CHIP,
+            memory_policy: MemoryPolicy::Wrap,
+            stack_depth: Some(Stack::SCHIP_DEPTH),
+            stack_policy: StackPolicy::Error,
+            key_policy: KeyPolicy::Mask,
+            seed: None,
+            engine: Engine::Interpreter,
         }
     }
 
-    pub fn font(&self) -> [u8; 80] {
-        self.font
+    pub fn font(&self) -> &Font {
+        &self.font
+    }
+
+    pub fn set_font(&mut self, font: Font) {
+        self.font = font;
+    }
+
+    pub fn font_address(&self) -> u16 {
+        self.font_address
+    }
+
+    pub fn set_font_address(&mut self, font_address: u16) {
+        self.font_address = font_address;
+    }
+
+    pub fn big_font(&self) -> &BigFont {
+        &self.big_font
+    }
+
+    pub fn set_big_font(&mut self, big_font: BigFont) {
+        self.big_font = big_font;
+    }
+
+    pub fn big_font_address(&self) -> u16 {
+        self.big_font_address
+    }
+
+    pub fn set_big_font_address(&mut self, big_font_address: u16) {
+        self.big_font_address = big_font_address;
+    }
+
+    pub fn quirks(&self) -> &Quirks {
+        &self.quirks
+    }
+
+    pub fn quirks_mut(&mut self) -> &mut Quirks {
+        &mut self.quirks
+    }
+
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+    }
+
+    pub fn memory_size(&self) -> usize {
+        self.memory_size
+    }
+
+    pub fn set_memory_size(&mut self, memory_size: usize) {
+        self.memory_size = memory_size;
+    }
+
+    pub fn memory_policy(&self) -> MemoryPolicy {
+        self.memory_policy
     }
-}
\ No newline at end of file
+
+    pub fn set_memory_policy(&mut self, memory_policy: MemoryPolicy) {
+        self.memory_policy = memory_policy;
+    }
+
+    // None for an unlimited stack.
+    pub fn stack_depth(&self) -> Option<usize> {
+        self.stack_depth
+    }
+
+    pub fn set_stack_depth(&mut self, stack_depth: Option<usize>) {
+        self.stack_depth = stack_depth;
+    }
+
+    pub fn stack_policy(&self) -> StackPolicy {
+        self.stack_policy
+    }
+
+    pub fn set_stack_policy(&mut self, stack_policy: StackPolicy) {
+        self.stack_policy = stack_policy;
+    }
+
+    pub fn key_policy(&self) -> KeyPolicy {
+        self.key_policy
+    }
+
+    pub fn set_key_policy(&mut self, key_policy: KeyPolicy) {
+        self.key_policy = key_policy;
+    }
+
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    pub fn set_seed(&mut self, seed: Option<u64>) {
+        self.seed = seed;
+    }
+
+    pub fn engine(&self) -> Engine {
+        self.engine
+    }
+
+    pub fn set_engine(&mut self, engine: Engine) {
+        self.engine = engine;
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self::new()
+    }
+}
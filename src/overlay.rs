@@ -0,0 +1,250 @@
+// An on-screen 4x4 hex keypad for frontends to draw beside or over the CHIP-8
+// screen, so new players can see what's what. Keys light up while they're down
+// and get a bright outline while the ROM is checking them, and `key_at` turns a
+// click or touch on the frame back into a key.
+
+use core::fmt;
+
+use alloc::format;
+use alloc::string::String;
+
+use crate::{Font, Key, Keypad, Palette};
+
+// The keys in the order they sit on the COSMAC VIP's keypad.
+const LAYOUT: [[Key; 4]; 4] = [
+    [Key::K1, Key::K2, Key::K3, Key::KC],
+    [Key::K4, Key::K5, Key::K6, Key::KD],
+    [Key::K7, Key::K8, Key::K9, Key::KE],
+    [Key::KA, Key::K0, Key::KB, Key::KF],
+];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OverlayMode {
+    #[default]
+    Off,
+    // A square panel to the right of the screen, which makes the frame wider.
+    Side,
+    // In the middle of the screen, with the game dimmed behind it.
+    Over,
+}
+
+impl OverlayMode {
+    pub fn parse(mode: &str) -> Result<OverlayMode, String> {
+        match mode {
+            "off" => Ok(OverlayMode::Off),
+            "side" => Ok(OverlayMode::Side),
+            "over" => Ok(OverlayMode::Over),
+            _ => Err(format!("Invalid keypad overlay \"{}\", expected off, side or over.", mode)),
+        }
+    }
+
+    // What the hotkey cycles through.
+    pub fn next(self) -> OverlayMode {
+        match self {
+            OverlayMode::Off => OverlayMode::Side,
+            OverlayMode::Side => OverlayMode::Over,
+            OverlayMode::Over => OverlayMode::Off,
+        }
+    }
+}
+
+impl fmt::Display for OverlayMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OverlayMode::Off => write!(f, "off"),
+            OverlayMode::Side => write!(f, "side"),
+            OverlayMode::Over => write!(f, "over"),
+        }
+    }
+}
+
+// Lays the keypad out for a screen of `width` x `height` CHIP-8 pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeypadOverlay {
+    mode: OverlayMode,
+    width: usize,
+    height: usize,
+}
+
+impl KeypadOverlay {
+    pub fn new(mode: OverlayMode, width: usize, height: usize) -> KeypadOverlay {
+        KeypadOverlay { mode, width, height }
+    }
+
+    pub fn mode(&self) -> OverlayMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: OverlayMode) {
+        self.mode = mode;
+    }
+
+    // The frame the screen and the overlay are drawn into.
+    pub fn frame_size(&self) -> (usize, usize) {
+        match self.mode {
+            OverlayMode::Side => (self.width + self.height, self.height),
+            _ => (self.width, self.height),
+        }
+    }
+
+    // Size of one key, the 4x5 font blown up to fit with a border around it.
+    fn cell(&self) -> usize {
+        (self.height / 4).max(8)
+    }
+
+    // Top left corner of the keypad in the frame.
+    fn origin(&self) -> Option<(usize, usize)> {
+        let size = self.cell() * 4;
+        match self.mode {
+            OverlayMode::Off => None,
+            OverlayMode::Side => Some((self.width + (self.height.saturating_sub(size)) / 2, self.height.saturating_sub(size) / 2)),
+            OverlayMode::Over => Some((self.width.saturating_sub(size) / 2, self.height.saturating_sub(size) / 2)),
+        }
+    }
+
+    // The key under a point in the frame, for mouse clicks and touches.
+    pub fn key_at(&self, x: usize, y: usize) -> Option<Key> {
+        let (left, top) = self.origin()?;
+        let cell = self.cell();
+        let column = x.checked_sub(left)? / cell;
+        let row = y.checked_sub(top)? / cell;
+        LAYOUT.get(row)?.get(column).copied()
+    }
+
+    // Copies `screen` (the CHIP-8 screen as RGBA, `width` x `height`) into `frame`
+    // (RGBA, `frame_size()`) and draws the keypad on top.
+    pub fn render(&self, screen: &[u8], keypad: &Keypad, palette: &Palette, frame: &mut [u8]) {
+        let (frame_width, _) = self.frame_size();
+        let background = palette.background();
+        for (y, row) in frame.chunks_exact_mut(frame_width * 4).enumerate().take(self.height) {
+            let screen_row = &screen[y * self.width * 4..(y + 1) * self.width * 4];
+            row[..self.width * 4].copy_from_slice(screen_row);
+            for pixel in row[self.width * 4..].chunks_exact_mut(4) {
+                pixel.copy_from_slice(&background);
+            }
+        }
+        let (left, top) = match self.origin() {
+            Some(origin) => origin,
+            None => return,
+        };
+        let cell = self.cell();
+        let scale = cell / 8;
+        let font = Font::Octo;
+        for (row, keys) in LAYOUT.iter().enumerate() {
+            for (column, &key) in keys.iter().enumerate() {
+                let down = keypad.is_down(key);
+                let (border, glyph) = match (down, keypad.was_polled(key)) {
+                    (true, _) => (1.0, 0.0),
+                    (false, true) => (1.0, 1.0),
+                    (false, false) => (0.3, 0.6),
+                };
+                let glyph_rows = &font.data()[key.index() * 5..key.index() * 5 + 5];
+                let (glyph_x, glyph_y) = ((cell - 4 * scale) / 2, (cell - 5 * scale) / 2);
+                for cy in 0..cell {
+                    for cx in 0..cell {
+                        let (x, y) = (left + column * cell + cx, top + row * cell + cy);
+                        let pixel = &mut frame[(y * frame_width + x) * 4..][..4];
+                        let edge = cx == 0 || cy == 0 || cx == cell - 1 || cy == cell - 1;
+                        let lit = cx >= glyph_x && cy >= glyph_y && (cx - glyph_x) / scale < 4 && (cy - glyph_y) / scale < 5
+                            && glyph_rows[(cy - glyph_y) / scale] & (0x80 >> ((cx - glyph_x) / scale)) != 0;
+                        let t = if edge {
+                            border
+                        } else if lit {
+                            glyph
+                        } else if down {
+                            1.0
+                        } else {
+                            0.0
+                        };
+                        // Dim the game behind the keys so the labels can be read.
+                        let behind = mix(background, [pixel[0], pixel[1], pixel[2], pixel[3]], 0.3);
+                        pixel.copy_from_slice(&mix(behind, palette.foreground(), t));
+                    }
+                }
+            }
+        }
+    }
+}
+
+// No f32::round without std, the + 0.5 rounds since nothing here is negative.
+fn mix(from: [u8; 4], to: [u8; 4], t: f32) -> [u8; 4] {
+    let mut color = [0; 4];
+    for c in 0..4 {
+        color[c] = (from[c] as f32 + (to[c] as f32 - from[c] as f32) * t + 0.5) as u8;
+    }
+    color
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn frame(overlay: &KeypadOverlay, keypad: &Keypad) -> alloc::vec::Vec<u8> {
+        let palette = Palette::classic();
+        let screen = vec![palette.foreground(); 64 * 32].concat();
+        let (width, height) = overlay.frame_size();
+        let mut frame = vec![0; width * height * 4];
+        overlay.render(&screen, keypad, &palette, &mut frame);
+        frame
+    }
+
+    fn pixel(frame: &[u8], frame_width: usize, x: usize, y: usize) -> [u8; 4] {
+        frame[(y * frame_width + x) * 4..][..4].try_into().unwrap()
+    }
+
+    #[test]
+    fn modes_parse_and_cycle() {
+        for mode in [OverlayMode::Off, OverlayMode::Side, OverlayMode::Over] {
+            assert_eq!(OverlayMode::parse(&mode.to_string()), Ok(mode));
+        }
+        assert!(OverlayMode::parse("top").is_err());
+        assert_eq!(OverlayMode::Over.next(), OverlayMode::Off);
+    }
+
+    #[test]
+    fn keys_under_the_pointer() {
+        let side = KeypadOverlay::new(OverlayMode::Side, 64, 32);
+        assert_eq!(side.frame_size(), (96, 32));
+        assert_eq!(side.key_at(10, 10), None);
+        assert_eq!(side.key_at(64, 0), Some(Key::K1));
+        assert_eq!(side.key_at(95, 31), Some(Key::KF));
+        assert_eq!(side.key_at(72 + 3, 24 + 7), Some(Key::K0));
+        assert_eq!(side.key_at(96, 0), None);
+        let over = KeypadOverlay::new(OverlayMode::Over, 128, 64);
+        assert_eq!(over.frame_size(), (128, 64));
+        assert_eq!(over.key_at(32, 0), Some(Key::K1));
+        assert_eq!(over.key_at(31, 0), None);
+        assert_eq!(over.key_at(32 + 16, 16), Some(Key::K5));
+        assert_eq!(KeypadOverlay::new(OverlayMode::Off, 64, 32).key_at(32, 16), None);
+    }
+
+    #[test]
+    fn off_copies_the_screen() {
+        let overlay = KeypadOverlay::new(OverlayMode::Off, 64, 32);
+        let frame = frame(&overlay, &Keypad::new());
+        assert!(frame.chunks(4).all(|pixel| pixel == Palette::classic().foreground()));
+    }
+
+    #[test]
+    fn keys_light_up() {
+        let palette = Palette::classic();
+        let overlay = KeypadOverlay::new(OverlayMode::Side, 64, 32);
+        let mut keypad = Keypad::new();
+        let idle = frame(&overlay, &keypad);
+        // The screen is copied to the left and the panel's background is the palette's.
+        assert_eq!(pixel(&idle, 96, 0, 0), palette.foreground());
+        // Inside the 1 key, away from its border and digit.
+        assert_eq!(pixel(&idle, 96, 64 + 1, 6), palette.background());
+        keypad.set(Key::K1, true);
+        let down = frame(&overlay, &keypad);
+        assert_eq!(pixel(&down, 96, 64 + 1, 6), palette.foreground());
+        keypad.set(Key::K1, false);
+        keypad.poll(Key::K1);
+        let polled = frame(&overlay, &keypad);
+        assert_eq!(pixel(&polled, 96, 64, 0), palette.foreground());
+        assert_ne!(pixel(&idle, 96, 64, 0), palette.foreground());
+        assert_eq!(pixel(&polled, 96, 64 + 1, 6), palette.background());
+    }
+}
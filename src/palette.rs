@@ -0,0 +1,166 @@
+// Colours used to turn the display buffer into RGBA. Index 0 is the background,
+// 1 and 2 are the two bitplanes and 3 is where both planes overlap.
+
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::Display;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Palette {
+    name: String,
+    colors: [[u8; 4]; 4],
+}
+
+impl Palette {
+    pub const BUILT_IN: [&'static str; 5] = ["classic", "green", "amber", "gameboy", "high-contrast"];
+
+    pub fn new(name: &str, colors: [[u8; 4]; 4]) -> Palette {
+        Palette {
+            name: name.to_owned(),
+            colors,
+        }
+    }
+
+    pub fn classic() -> Palette {
+        Palette::new("classic", [rgb(0x000000), rgb(0xFFFFFF), rgb(0xAAAAAA), rgb(0x555555)])
+    }
+
+    pub fn from_name(name: &str) -> Option<Palette> {
+        let colors = match name {
+            "classic" => return Some(Palette::classic()),
+            // Green phosphor monochrome monitor.
+            "green" => [rgb(0x001100), rgb(0x33FF33), rgb(0x119911), rgb(0x88FF88)],
+            // Amber monochrome monitor.
+            "amber" => [rgb(0x1A0F00), rgb(0xFFB000), rgb(0x996A00), rgb(0xFFD480)],
+            // Original Game Boy (DMG) LCD.
+            "gameboy" => [rgb(0x9BBC0F), rgb(0x0F380F), rgb(0x306230), rgb(0x8BAC0F)],
+            "high-contrast" => [rgb(0x000000), rgb(0xFFFF00), rgb(0x00FFFF), rgb(0xFFFFFF)],
+            _ => return None,
+        };
+        Some(Palette::new(name, colors))
+    }
+
+    // Comma separated hex colours, either "background,foreground" or all four.
+    pub fn from_hex(hex: &str) -> Result<Palette, String> {
+        let colors = hex
+            .split(',')
+            .map(|color| {
+                let color = color.trim().trim_start_matches('#');
+                if color.len() != 6 {
+                    return Err(format!("Invalid colour \"{}\", expected RRGGBB.", color));
+                }
+                u32::from_str_radix(color, 16)
+                    .map(rgb)
+                    .map_err(|_| format!("Invalid colour \"{}\", expected RRGGBB.", color))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        let colors = match colors.as_slice() {
+            [background, foreground] => [*background, *foreground, *foreground, *foreground],
+            [a, b, c, d] => [*a, *b, *c, *d],
+            _ => return Err(String::from("A palette needs 2 or 4 colours.")),
+        };
+        Ok(Palette::new(hex, colors))
+    }
+
+    // Accepts either a built-in palette name or hex colours.
+    pub fn parse(palette: &str) -> Result<Palette, String> {
+        match Palette::from_name(palette) {
+            Some(palette) => Ok(palette),
+            None => Palette::from_hex(palette),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn color(&self, pixel: u8) -> [u8; 4] {
+        self.colors[(pixel & 0b11) as usize]
+    }
+
+    pub fn background(&self) -> [u8; 4] {
+        self.colors[0]
+    }
+
+    pub fn foreground(&self) -> [u8; 4] {
+        self.colors[1]
+    }
+
+    // The display as RGBA with every pixel blown up to `scale` x `scale`, row-major.
+    pub fn frame(&self, display: &Display, scale: usize) -> Vec<u8> {
+        let width = display.width() as usize;
+        let height = display.height() as usize;
+        let mut frame = Vec::with_capacity(width * height * scale * scale * 4);
+        let mut row = Vec::with_capacity(width * scale * 4);
+        for y in 0..height {
+            row.clear();
+            for x in 0..width {
+                let color = self.color(display.pixel(x, y));
+                for _ in 0..scale {
+                    row.extend_from_slice(&color);
+                }
+            }
+            for _ in 0..scale {
+                frame.extend_from_slice(&row);
+            }
+        }
+        frame
+    }
+
+    // The built-in palette after this one, for cycling through them with a hotkey.
+    // Custom palettes go back to the first built-in one.
+    pub fn next(&self) -> Palette {
+        let index = Palette::BUILT_IN
+            .iter()
+            .position(|&name| name == self.name)
+            .map_or(0, |i| (i + 1) % Palette::BUILT_IN.len());
+        Palette::from_name(Palette::BUILT_IN[index]).expect("Built-in palettes always exist.")
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self::classic()
+    }
+}
+
+fn rgb(color: u32) -> [u8; 4] {
+    let [_, r, g, b] = color.to_be_bytes();
+    [r, g, b, 0xFF]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn built_in_palettes_exist() {
+        for name in Palette::BUILT_IN {
+            assert_eq!(Palette::from_name(name).unwrap().name(), name);
+        }
+    }
+
+    #[test]
+    fn parses_hex_colours() {
+        let palette = Palette::parse("#102030, 405060").unwrap();
+        assert_eq!(palette.background(), [0x10, 0x20, 0x30, 0xFF]);
+        assert_eq!(palette.color(3), [0x40, 0x50, 0x60, 0xFF]);
+        let palette = Palette::parse("000000,111111,222222,333333").unwrap();
+        assert_eq!(palette.color(2), [0x22, 0x22, 0x22, 0xFF]);
+        assert!(Palette::parse("000000").is_err());
+        assert!(Palette::parse("nope").is_err());
+    }
+
+    #[test]
+    fn next_cycles_through_built_ins() {
+        let mut palette = Palette::from_hex("000000,ffffff").unwrap();
+        for name in Palette::BUILT_IN.iter().chain(Palette::BUILT_IN.iter()) {
+            palette = palette.next();
+            assert_eq!(palette.name(), *name);
+        }
+    }
+}
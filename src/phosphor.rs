@@ -0,0 +1,244 @@
+// Post-processing that makes XOR-flickering sprites easier on the eyes by
+// keeping pixels lit for a little while after they are erased. Works off the
+// display buffer each frame, the display itself is left alone.
+
+use std::collections::VecDeque;
+
+use crate::{Display, Palette};
+
+// Anything dimmer than this rounds to the background colour, so it's snapped to
+// 0 to let `fading` end instead of halving forever.
+const INVISIBLE: f32 = 1.0 / 255.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Persistence {
+    // Show the raw display buffer.
+    Off,
+    // Average the last N frames.
+    Blend(usize),
+    // Lit pixels fade out by this fraction every frame, like an old CRT's phosphor.
+    Decay(f32),
+}
+
+impl Persistence {
+    // "off", "blend:N" or "decay:RATE".
+    pub fn parse(persistence: &str) -> Result<Persistence, String> {
+        let invalid = || format!("Invalid persistence \"{}\", expected off, blend:N or decay:RATE.", persistence);
+        match persistence.split_once(':') {
+            None if persistence == "off" => Ok(Persistence::Off),
+            Some(("blend", frames)) => match frames.parse() {
+                Ok(frames) if frames > 0 => Ok(Persistence::Blend(frames)),
+                _ => Err(invalid()),
+            },
+            Some(("decay", rate)) => match rate.parse() {
+                Ok(rate) if (0.0..=1.0).contains(&rate) => Ok(Persistence::Decay(rate)),
+                _ => Err(invalid()),
+            },
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl std::fmt::Display for Persistence {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Persistence::Off => write!(f, "off"),
+            Persistence::Blend(frames) => write!(f, "blend:{}", frames),
+            Persistence::Decay(rate) => write!(f, "decay:{}", rate),
+        }
+    }
+}
+
+pub struct Phosphor {
+    persistence: Persistence,
+    width: usize,
+    height: usize,
+    // Brightness of every pixel from 0 to 1, row-major.
+    intensity: Vec<f32>,
+    // Last lit value of every pixel so faded pixels keep their bitplane colour.
+    color: Vec<u8>,
+    history: VecDeque<Vec<u8>>,
+    // Set when every pixel needs updating, not just the dirty rows.
+    stale: bool,
+}
+
+impl Phosphor {
+    pub fn new(persistence: Persistence) -> Phosphor {
+        Phosphor {
+            persistence,
+            width: 0,
+            height: 0,
+            intensity: Vec::new(),
+            color: Vec::new(),
+            history: VecDeque::new(),
+            stale: true,
+        }
+    }
+
+    pub fn persistence(&self) -> Persistence {
+        self.persistence
+    }
+
+    pub fn set_persistence(&mut self, persistence: Persistence) {
+        self.persistence = persistence;
+        self.history.clear();
+        self.stale = true;
+    }
+
+    // Call once per emulated frame.
+    pub fn update(&mut self, display: &Display) {
+        let width = display.width() as usize;
+        let height = display.height() as usize;
+        if width != self.width || height != self.height {
+            self.width = width;
+            self.height = height;
+            self.intensity = vec![0.0; width * height];
+            self.color = vec![0; width * height];
+            self.history.clear();
+            self.stale = true;
+        }
+
+        if self.persistence == Persistence::Off {
+            // Nothing fades, so only the rows that changed need looking at.
+            for y in 0..height {
+                if !self.stale && !display.is_row_dirty(y) {
+                    continue;
+                }
+                for x in 0..width {
+                    let pixel = display.pixel(x, y);
+                    self.intensity[y * width + x] = if pixel != 0 { 1.0 } else { 0.0 };
+                    if pixel != 0 {
+                        self.color[y * width + x] = pixel;
+                    }
+                }
+            }
+            self.stale = false;
+            return;
+        }
+
+        let mut frame = vec![0u8; width * height];
+        for y in 0..height {
+            for x in 0..width {
+                let pixel = display.pixel(x, y);
+                frame[y * width + x] = pixel;
+                if pixel != 0 {
+                    self.color[y * width + x] = pixel;
+                }
+            }
+        }
+        // Off needs a full update when it's switched back on.
+        self.stale = true;
+
+        match self.persistence {
+            Persistence::Off => unreachable!(),
+            Persistence::Blend(frames) => {
+                self.history.push_back(frame);
+                while self.history.len() > frames {
+                    self.history.pop_front();
+                }
+                let count = self.history.len() as f32;
+                for (i, intensity) in self.intensity.iter_mut().enumerate() {
+                    let lit = self.history.iter().filter(|frame| frame[i] != 0).count();
+                    *intensity = lit as f32 / count;
+                }
+            }
+            Persistence::Decay(rate) => {
+                for (intensity, &pixel) in self.intensity.iter_mut().zip(&frame) {
+                    *intensity = if pixel != 0 { 1.0 } else { *intensity * (1.0 - rate) };
+                    if *intensity < INVISIBLE {
+                        *intensity = 0.0;
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn intensity(&self, x: usize, y: usize) -> f32 {
+        self.intensity[y * self.width + x]
+    }
+
+    // True while some pixel is part way faded, so the frame keeps changing without any draws.
+    pub fn fading(&self) -> bool {
+        self.intensity.iter().any(|&i| i > 0.0 && i < 1.0)
+    }
+
+    // Writes the processed frame as RGBA into `frame`, one pixel per 4 bytes, row-major.
+    pub fn render(&self, palette: &Palette, frame: &mut [u8]) {
+        let background = palette.background();
+        for (i, pixel) in frame.chunks_exact_mut(4).enumerate().take(self.intensity.len()) {
+            let color = palette.color(self.color[i]);
+            let t = self.intensity[i];
+            for c in 0..4 {
+                pixel[c] = (background[c] as f32 + (color[c] as f32 - background[c] as f32) * t).round() as u8;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn display_with_pixel() -> Display {
+        let mut display = Display::new();
+        display.draw(&[0x80], 0, 0);
+        display
+    }
+
+    #[test]
+    fn decay_fades_erased_pixels() {
+        let mut phosphor = Phosphor::new(Persistence::Decay(0.5));
+        let mut display = display_with_pixel();
+        phosphor.update(&display);
+        assert_eq!(phosphor.intensity(0, 0), 1.0);
+        display.clear_screen();
+        phosphor.update(&display);
+        assert_eq!(phosphor.intensity(0, 0), 0.5);
+        phosphor.update(&display);
+        assert_eq!(phosphor.intensity(0, 0), 0.25);
+        assert!(phosphor.fading());
+        // 1/256 is too dim to see.
+        for _ in 0..6 {
+            phosphor.update(&display);
+        }
+        assert_eq!(phosphor.intensity(0, 0), 0.0);
+        assert!(!phosphor.fading());
+    }
+
+    #[test]
+    fn blend_averages_the_last_frames() {
+        let mut phosphor = Phosphor::new(Persistence::Blend(2));
+        let mut display = display_with_pixel();
+        phosphor.update(&display);
+        display.clear_screen();
+        phosphor.update(&display);
+        assert_eq!(phosphor.intensity(0, 0), 0.5);
+        phosphor.update(&display);
+        assert_eq!(phosphor.intensity(0, 0), 0.0);
+    }
+
+    #[test]
+    fn renders_between_background_and_foreground() {
+        let mut phosphor = Phosphor::new(Persistence::Decay(0.5));
+        let mut display = display_with_pixel();
+        phosphor.update(&display);
+        display.clear_screen();
+        phosphor.update(&display);
+        let palette = Palette::from_hex("000000,ffffff").unwrap();
+        let mut frame = vec![0; 64 * 32 * 4];
+        phosphor.render(&palette, &mut frame);
+        assert_eq!(&frame[0..4], &[0x80, 0x80, 0x80, 0xFF]);
+        assert_eq!(&frame[4..8], &[0x00, 0x00, 0x00, 0xFF]);
+    }
+
+    #[test]
+    fn parses_persistence() {
+        assert_eq!(Persistence::parse("off"), Ok(Persistence::Off));
+        assert_eq!(Persistence::parse("blend:3"), Ok(Persistence::Blend(3)));
+        assert_eq!(Persistence::parse("decay:0.25"), Ok(Persistence::Decay(0.25)));
+        assert!(Persistence::parse("blend:0").is_err());
+        assert!(Persistence::parse("decay:2").is_err());
+        assert!(Persistence::parse("glow").is_err());
+    }
+}
@@ -0,0 +1,105 @@
+// Behaviours that differ between CHIP-8 implementations. Games written for one
+// interpreter can break on another, so these are grouped into profiles.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SpriteEdge {
+    // Pixels past the right/bottom edge are dropped.
+    Clip,
+    // Pixels past the right/bottom edge come back in on the opposite side.
+    Wrap,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KeyWait {
+    // FX0A finishes once a key is pressed and let go, like the COSMAC VIP.
+    Release,
+    // FX0A finishes as soon as a key goes down.
+    Press,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Quirks {
+    // Original CHIP-8 waited for the vertical blank before drawing, so only one DXYN ran per frame.
+    display_wait: bool,
+    sprite_edge: SpriteEdge,
+    key_wait: KeyWait,
+}
+
+impl Quirks {
+    pub fn new() -> Quirks {
+        Quirks {
+            display_wait: false,
+            sprite_edge: SpriteEdge::Clip,
+            key_wait: KeyWait::Release,
+        }
+    }
+
+    // COSMAC VIP.
+    pub fn chip8() -> Quirks {
+        Quirks {
+            display_wait: true,
+            sprite_edge: SpriteEdge::Clip,
+            key_wait: KeyWait::Release,
+        }
+    }
+
+    // SUPER-CHIP 1.1 on the HP48.
+    pub fn schip() -> Quirks {
+        Quirks {
+            display_wait: false,
+            sprite_edge: SpriteEdge::Clip,
+            key_wait: KeyWait::Release,
+        }
+    }
+
+    // Octo's XO-CHIP.
+    pub fn xo_chip() -> Quirks {
+        Quirks {
+            display_wait: false,
+            sprite_edge: SpriteEdge::Wrap,
+            key_wait: KeyWait::Release,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Quirks> {
+        match name {
+            "chip8" | "vip" => Some(Quirks::chip8()),
+            "schip" => Some(Quirks::schip()),
+            "xochip" | "xo-chip" => Some(Quirks::xo_chip()),
+            _ => None,
+        }
+    }
+
+    pub fn display_wait(&self) -> bool {
+        self.display_wait
+    }
+
+    pub fn set_display_wait(&mut self, display_wait: bool) {
+        self.display_wait = display_wait;
+    }
+
+    pub fn sprite_edge(&self) -> SpriteEdge {
+        self.sprite_edge
+    }
+
+    pub fn set_sprite_edge(&mut self, sprite_edge: SpriteEdge) {
+        self.sprite_edge = sprite_edge;
+    }
+
+    pub fn key_wait(&self) -> KeyWait {
+        self.key_wait
+    }
+
+    pub fn set_key_wait(&mut self, key_wait: KeyWait) {
+        self.key_wait = key_wait;
+    }
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
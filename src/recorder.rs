@@ -0,0 +1,201 @@
+// Records every frame of the display, either as an animated GIF or as a raw
+// RGBA stream for piping into an external encoder, for example:
+//   chip-egg rom.ch8 --record-raw - | ffmpeg -f rawvideo -pix_fmt rgba -s 256x128 -r 60 -i - out.mp4
+
+use std::io::Write;
+
+use crate::{Display, Palette};
+
+// Frames are recorded at the timer rate.
+const FPS: u64 = 60;
+
+pub enum Recorder<W: Write> {
+    Gif(GifRecorder<W>),
+    Raw(RawRecorder<W>),
+}
+
+impl<W: Write> Recorder<W> {
+    pub fn gif(writer: W, palette: &Palette, scale: usize, display: &Display) -> Result<Recorder<W>, String> {
+        GifRecorder::new(writer, palette, scale, display).map(Recorder::Gif)
+    }
+
+    pub fn raw(writer: W, palette: &Palette, scale: usize) -> Recorder<W> {
+        Recorder::Raw(RawRecorder::new(writer, palette, scale))
+    }
+
+    // Call once per frame.
+    pub fn record(&mut self, display: &Display) -> Result<(), String> {
+        match self {
+            Recorder::Gif(recorder) => recorder.record(display),
+            Recorder::Raw(recorder) => recorder.record(display),
+        }
+    }
+
+    pub fn finish(self) -> Result<W, String> {
+        match self {
+            Recorder::Gif(recorder) => recorder.finish(),
+            Recorder::Raw(recorder) => recorder.finish(),
+        }
+    }
+}
+
+pub struct GifRecorder<W: Write> {
+    encoder: gif::Encoder<W>,
+    width: u16,
+    height: u16,
+    scale: usize,
+    // Identical frames in a row get merged into one longer frame, so the last
+    // frame is held back until a different one comes along.
+    pending: Option<Vec<u8>>,
+    // Frame numbers of the start of the pending frame and the current frame.
+    pending_start: u64,
+    frames: u64,
+}
+
+impl<W: Write> GifRecorder<W> {
+    pub fn new(writer: W, palette: &Palette, scale: usize, display: &Display) -> Result<GifRecorder<W>, String> {
+        let scale = scale.max(1);
+        let width = display.width() as usize * scale;
+        let height = display.height() as usize * scale;
+        if width > u16::MAX as usize || height > u16::MAX as usize {
+            return Err(String::from("Recording is too big for a GIF, use a smaller scale."));
+        }
+        // The palette's four colours are the GIF's colour table, pixel values index straight into it.
+        let colors: Vec<u8> = (0..4).flat_map(|i| palette.color(i)[..3].to_vec()).collect();
+        let mut encoder = gif::Encoder::new(writer, width as u16, height as u16, &colors).map_err(|e| e.to_string())?;
+        encoder.set_repeat(gif::Repeat::Infinite).map_err(|e| e.to_string())?;
+        Ok(GifRecorder {
+            encoder,
+            width: width as u16,
+            height: height as u16,
+            scale,
+            pending: None,
+            pending_start: 0,
+            frames: 0,
+        })
+    }
+
+    pub fn record(&mut self, display: &Display) -> Result<(), String> {
+        let pixels = indexed(display, self.scale);
+        if self.pending.as_ref() != Some(&pixels) {
+            self.write_pending()?;
+            self.pending = Some(pixels);
+            self.pending_start = self.frames;
+        }
+        self.frames += 1;
+        Ok(())
+    }
+
+    fn write_pending(&mut self) -> Result<(), String> {
+        if let Some(pixels) = self.pending.take() {
+            // GIF delays are in hundredths of a second, working them out from the absolute
+            // frame numbers stops rounding errors from adding up.
+            let start = self.pending_start * 100 / FPS;
+            let end = self.frames * 100 / FPS;
+            let mut frame = gif::Frame::from_indexed_pixels(self.width, self.height, &pixels, None);
+            frame.delay = (end - start).clamp(1, u16::MAX as u64) as u16;
+            self.encoder.write_frame(&frame).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<W, String> {
+        self.write_pending()?;
+        self.encoder.into_inner().map_err(|e| e.to_string())
+    }
+}
+
+pub struct RawRecorder<W: Write> {
+    writer: W,
+    palette: Palette,
+    scale: usize,
+}
+
+impl<W: Write> RawRecorder<W> {
+    pub fn new(writer: W, palette: &Palette, scale: usize) -> RawRecorder<W> {
+        RawRecorder {
+            writer,
+            palette: palette.clone(),
+            scale: scale.max(1),
+        }
+    }
+
+    // Every frame is written, even repeats, so the stream has a constant frame rate.
+    pub fn record(&mut self, display: &Display) -> Result<(), String> {
+        self.writer
+            .write_all(&self.palette.frame(display, self.scale))
+            .map_err(|e| e.to_string())
+    }
+
+    pub fn finish(mut self) -> Result<W, String> {
+        self.writer.flush().map_err(|e| e.to_string())?;
+        Ok(self.writer)
+    }
+}
+
+fn indexed(display: &Display, scale: usize) -> Vec<u8> {
+    let width = display.width() as usize;
+    let height = display.height() as usize;
+    let mut pixels = Vec::with_capacity(width * height * scale * scale);
+    let mut row = Vec::with_capacity(width * scale);
+    for y in 0..height {
+        row.clear();
+        for x in 0..width {
+            row.extend(std::iter::repeat_n(display.pixel(x, y) & 0b11, scale));
+        }
+        for _ in 0..scale {
+            pixels.extend_from_slice(&row);
+        }
+    }
+    pixels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames(gif: &[u8]) -> Vec<(u16, Vec<u8>)> {
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::Indexed);
+        let mut decoder = options.read_info(gif).unwrap();
+        let mut frames = Vec::new();
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            frames.push((frame.delay, frame.buffer.to_vec()));
+        }
+        frames
+    }
+
+    #[test]
+    fn merges_identical_frames() {
+        let palette = Palette::classic();
+        let mut display = Display::new();
+        let mut recorder = Recorder::gif(Vec::new(), &palette, 1, &display).unwrap();
+        for _ in 0..6 {
+            recorder.record(&display).unwrap();
+        }
+        display.draw(&[0x80], 0, 0);
+        for _ in 0..3 {
+            recorder.record(&display).unwrap();
+        }
+        let gif = recorder.finish().unwrap();
+
+        let frames = frames(&gif);
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].0, 10);
+        assert_eq!(frames[1].0, 5);
+        assert_eq!(frames[0].1[0], 0);
+        assert_eq!(frames[1].1[0], 1);
+    }
+
+    #[test]
+    fn raw_writes_every_frame() {
+        let palette = Palette::classic();
+        let display = Display::new();
+        let mut recorder = Recorder::raw(Vec::new(), &palette, 2);
+        recorder.record(&display).unwrap();
+        recorder.record(&display).unwrap();
+        let raw = recorder.finish().unwrap();
+        assert_eq!(raw.len(), 2 * 128 * 64 * 4);
+        assert_eq!(&raw[..4], &palette.background());
+    }
+}
@@ -0,0 +1,57 @@
+// A tiny xorshift64* generator for CXNN. The core can't rely on a thread local
+// RNG without std, and a seedable one makes runs reproducible.
+
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        // Xorshift gets stuck on 0.
+        Rng(if seed == 0 { 0x9E37_79B9_7F4A_7C15 } else { seed })
+    }
+
+    #[cfg(feature = "std")]
+    pub fn from_entropy() -> Rng {
+        Rng::new(fastrand::u64(..))
+    }
+
+    #[cfg(not(feature = "std"))]
+    pub fn from_entropy() -> Rng {
+        Rng::new(0)
+    }
+
+    // Never 0, for save states.
+    pub(crate) fn state(&self) -> u64 {
+        self.0
+    }
+
+    pub fn next_u8(&mut self) -> u8 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        (self.0.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 56) as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_numbers() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        for _ in 0..100 {
+            assert_eq!(a.next_u8(), b.next_u8());
+        }
+    }
+
+    #[test]
+    fn covers_every_byte() {
+        let mut rng = Rng::new(1);
+        let mut seen = [false; 256];
+        for _ in 0..10_000 {
+            seen[rng.next_u8() as usize] = true;
+        }
+        assert!(seen.iter().all(|&seen| seen));
+    }
+}
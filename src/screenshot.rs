@@ -0,0 +1,103 @@
+// PNG export of the display, used by the screenshot hotkey and for comparing
+// against golden images in headless runs.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{Display, Palette};
+
+pub fn encode_png(display: &Display, palette: &Palette, scale: usize) -> Result<Vec<u8>, String> {
+    let scale = scale.max(1);
+    let mut png = Vec::new();
+    write_png(&mut png, display, palette, scale)?;
+    Ok(png)
+}
+
+pub fn save_png<P: AsRef<Path>>(path: P, display: &Display, palette: &Palette, scale: usize) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| e.to_string())?;
+    write_png(BufWriter::new(file), display, palette, scale.max(1))
+}
+
+// Saves to a file named after the current time in `dir` and returns its path.
+pub fn save_screenshot<P: AsRef<Path>>(dir: P, display: &Display, palette: &Palette, scale: usize) -> Result<PathBuf, String> {
+    let path = timestamped_path(dir, "png");
+    save_png(&path, display, palette, scale)?;
+    Ok(path)
+}
+
+fn write_png<W: std::io::Write>(writer: W, display: &Display, palette: &Palette, scale: usize) -> Result<(), String> {
+    let width = display.width() as u32 * scale as u32;
+    let height = display.height() as u32 * scale as u32;
+    let mut encoder = png::Encoder::new(writer, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    writer
+        .write_image_data(&palette.frame(display, scale))
+        .map_err(|e| e.to_string())
+}
+
+// "chip-egg-<timestamp>.<extension>" in `dir`.
+pub fn timestamped_path<P: AsRef<Path>>(dir: P, extension: &str) -> PathBuf {
+    dir.as_ref().join(format!("chip-egg-{}.{}", timestamp(), extension))
+}
+
+// UTC "YYYY-MM-DD_HH-MM-SS-mmm", sorts nicely in a file browser.
+fn timestamp() -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = now.as_secs();
+    let (year, month, day) = civil_from_days((secs / 86400) as i64);
+    format!(
+        "{:04}-{:02}-{:02}_{:02}-{:02}-{:02}-{:03}",
+        year, month, day,
+        secs / 3600 % 24, secs / 60 % 60, secs % 60,
+        now.subsec_millis()
+    )
+}
+
+// Days since 1970-01-01 to a calendar date, from Howard Hinnant's date algorithms.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_scaled_png_with_palette() {
+        let mut display = Display::new();
+        display.draw(&[0x80], 1, 0);
+        let palette = Palette::from_hex("102030,405060").unwrap();
+        let png = encode_png(&display, &palette, 2).unwrap();
+
+        let decoder = png::Decoder::new(png.as_slice());
+        let mut reader = decoder.read_info().unwrap();
+        let mut image = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut image).unwrap();
+        assert_eq!((info.width, info.height), (128, 64));
+        let pixel = |x: usize, y: usize| &image[(y * 128 + x) * 4..][..4];
+        assert_eq!(pixel(0, 0), [0x10, 0x20, 0x30, 0xFF]);
+        assert_eq!(pixel(2, 0), [0x40, 0x50, 0x60, 0xFF]);
+        assert_eq!(pixel(3, 1), [0x40, 0x50, 0x60, 0xFF]);
+        assert_eq!(pixel(4, 0), [0x10, 0x20, 0x30, 0xFF]);
+    }
+
+    #[test]
+    fn converts_days_to_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(11016), (2000, 2, 29));
+        assert_eq!(civil_from_days(20745), (2026, 10, 19));
+    }
+}
@@ -0,0 +1,188 @@
+use pixels::Pixels;
+use wgpu::util::DeviceExt;
+
+use crate::CrtEffects;
+
+// Draws the pixels texture to the surface through shaders/crt.wgsl instead of
+// the plain nearest scaling renderer. Lives in the library rather than the window
+// so the benches can run it on a headless adapter.
+pub struct CrtRenderer {
+    uniform_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    render_pipeline: wgpu::RenderPipeline,
+    texture_size: (f32, f32),
+    effects: CrtEffects,
+}
+
+impl CrtRenderer {
+    pub fn new(pixels: &Pixels, width: u32, height: u32, effects: CrtEffects) -> Result<CrtRenderer, String> {
+        let device = &pixels.context().device;
+        CrtRenderer::with_device(device, pixels.texture(), pixels.render_texture_format(), width, height, effects)
+    }
+
+    // Samples `texture` (`width` x `height`) and draws into targets of `format`. Fails
+    // when the adapter can't build the pipeline, the CPU copy in `apply_crt_effects`
+    // can stand in then.
+    pub fn with_device(
+        device: &wgpu::Device,
+        texture: &wgpu::Texture,
+        format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+        effects: CrtEffects,
+    ) -> Result<CrtRenderer, String> {
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let module = device.create_shader_module(wgpu::include_wgsl!("../shaders/crt.wgsl"));
+        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("crt_sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+        let texture_size = (width as f32, height as f32);
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("crt_uniform_buffer"),
+            contents: &locals(texture_size, effects),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("crt_bind_group_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("crt_bind_group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("crt_pipeline_layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("crt_pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        });
+
+        if let Some(e) = pollster::block_on(device.pop_error_scope()) {
+            return Err(e.to_string());
+        }
+        Ok(CrtRenderer {
+            uniform_buffer,
+            bind_group,
+            render_pipeline,
+            texture_size,
+            effects,
+        })
+    }
+
+    pub fn effects(&self) -> CrtEffects {
+        self.effects
+    }
+
+    pub fn set_effects(&mut self, queue: &wgpu::Queue, effects: CrtEffects) {
+        self.effects = effects;
+        queue.write_buffer(&self.uniform_buffer, 0, &locals(self.texture_size, effects));
+    }
+
+    // `clip_rect` is the scaled area of the surface, same as the scaling renderer uses.
+    pub fn render(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        render_target: &wgpu::TextureView,
+        clip_rect: (u32, u32, u32, u32),
+    ) {
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("crt_render_pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: render_target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+        let (x, y, width, height) = clip_rect;
+        rpass.set_pipeline(&self.render_pipeline);
+        rpass.set_bind_group(0, &self.bind_group, &[]);
+        rpass.set_viewport(x as f32, y as f32, width as f32, height as f32, 0.0, 1.0);
+        rpass.set_scissor_rect(x, y, width, height);
+        rpass.draw(0..3, 0..1);
+    }
+}
+
+// Matches the Locals struct in the shader.
+fn locals(texture_size: (f32, f32), effects: CrtEffects) -> [u8; 16] {
+    let mut bytes = [0; 16];
+    bytes[0..4].copy_from_slice(&texture_size.0.to_ne_bytes());
+    bytes[4..8].copy_from_slice(&texture_size.1.to_ne_bytes());
+    bytes[8..12].copy_from_slice(&effects.bits().to_ne_bytes());
+    bytes
+}
@@ -0,0 +1,263 @@
+// Runtime speed controls for frontends with their own event loop: pause, frame
+// advance, fast forward, slow motion and the instructions run per frame. The
+// frontend asks `frames` how many frames to run every time its 60Hz tick comes round.
+
+use alloc::format;
+use alloc::string::String;
+
+use crate::Palette;
+
+// Slow motion runs one frame every this many ticks.
+const SLOW_MOTION: u32 = 4;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Speed {
+    tick_rate: u16,
+    paused: bool,
+    // Frames asked for with frame advance that haven't run yet.
+    steps: u32,
+    fast_forward: bool,
+    fast_forward_rate: u16,
+    slow_motion: bool,
+    ticks: u32,
+}
+
+impl Speed {
+    pub fn new(tick_rate: u16) -> Speed {
+        Speed {
+            tick_rate: tick_rate.max(1),
+            paused: false,
+            steps: 0,
+            fast_forward: false,
+            fast_forward_rate: 4,
+            slow_motion: false,
+            ticks: 0,
+        }
+    }
+
+    // Instructions per frame.
+    pub fn tick_rate(&self) -> u16 {
+        self.tick_rate
+    }
+
+    // About an eighth more or less each time, at least 1.
+    pub fn faster(&mut self) {
+        self.tick_rate = self.tick_rate.saturating_add((self.tick_rate / 8).max(1));
+    }
+
+    pub fn slower(&mut self) {
+        self.tick_rate = self.tick_rate.saturating_sub((self.tick_rate / 8).max(1)).max(1);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        self.steps = 0;
+    }
+
+    // Runs a single frame on the next tick. Pauses first if it's running.
+    pub fn step(&mut self) {
+        if self.paused {
+            self.steps += 1;
+        } else {
+            self.paused = true;
+        }
+    }
+
+    pub fn fast_forward(&self) -> bool {
+        self.fast_forward
+    }
+
+    // For as long as the hotkey is held.
+    pub fn set_fast_forward(&mut self, fast_forward: bool) {
+        self.fast_forward = fast_forward;
+    }
+
+    // Frames per tick while fast forwarding.
+    pub fn fast_forward_rate(&self) -> u16 {
+        self.fast_forward_rate
+    }
+
+    pub fn set_fast_forward_rate(&mut self, rate: u16) {
+        self.fast_forward_rate = rate.max(1);
+    }
+
+    pub fn slow_motion(&self) -> bool {
+        self.slow_motion
+    }
+
+    pub fn toggle_slow_motion(&mut self) {
+        self.slow_motion = !self.slow_motion;
+        self.ticks = 0;
+    }
+
+    // Nothing paused, fast or slow, the instructions per frame can still be anything.
+    pub fn is_normal(&self) -> bool {
+        !self.paused && !self.fast_forward && !self.slow_motion
+    }
+
+    // How many frames to run this tick. Pausing wins over fast forward, which wins over slow motion.
+    pub fn frames(&mut self) -> u32 {
+        if self.paused {
+            let step = self.steps.min(1);
+            self.steps -= step;
+            return step;
+        }
+        if self.fast_forward {
+            return self.fast_forward_rate.into();
+        }
+        if self.slow_motion {
+            self.ticks += 1;
+            return u32::from(self.ticks.is_multiple_of(SLOW_MOTION));
+        }
+        1
+    }
+
+    // What the status indicator says: "||" paused, ">>x4" fast forward, "x1/4"
+    // slow motion, then the instructions per frame.
+    pub fn status(&self) -> String {
+        let mode = if self.paused {
+            String::from("|| ")
+        } else if self.fast_forward {
+            format!(">>x{} ", self.fast_forward_rate)
+        } else if self.slow_motion {
+            format!("x1/{} ", SLOW_MOTION)
+        } else {
+            String::new()
+        };
+        format!("{}{}", mode, self.tick_rate)
+    }
+}
+
+// 3x5 glyphs for the status indicator, the top bit of each row is the left pixel.
+fn glyph(c: char) -> [u8; 5] {
+    match c {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        '|' => [0b010, 0b010, 0b010, 0b010, 0b010],
+        '>' => [0b100, 0b110, 0b111, 0b110, 0b100],
+        'x' => [0b000, 0b101, 0b010, 0b101, 0b000],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        _ => [0; 5],
+    }
+}
+
+// Draws `text` in the top left corner of `frame` (RGBA, `frame_width` pixels a row)
+// on a background coloured box, so it shows up over whatever the game drew.
+pub fn draw_status(text: &str, palette: &Palette, frame: &mut [u8], frame_width: usize) {
+    let frame_height = frame.len() / 4 / frame_width.max(1);
+    let width = (text.chars().count() * 4 + 1).min(frame_width);
+    let height = 7.min(frame_height);
+    for y in 0..height {
+        for x in 0..width {
+            let (column, row) = (x.wrapping_sub(1), y.wrapping_sub(1));
+            let lit = row < 5
+                && column % 4 < 3
+                && text.chars().nth(column / 4).is_some_and(|c| glyph(c)[row] & (0b100 >> (column % 4)) != 0);
+            let color = if lit { palette.foreground() } else { palette.background() };
+            frame[(y * frame_width + x) * 4..][..4].copy_from_slice(&color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    fn ticks(speed: &mut Speed, ticks: usize) -> Vec<u32> {
+        (0..ticks).map(|_| speed.frames()).collect()
+    }
+
+    #[test]
+    fn pause_and_frame_advance() {
+        let mut speed = Speed::new(11);
+        assert_eq!(ticks(&mut speed, 2), [1, 1]);
+        speed.toggle_pause();
+        assert_eq!(ticks(&mut speed, 2), [0, 0]);
+        speed.step();
+        speed.step();
+        assert_eq!(ticks(&mut speed, 3), [1, 1, 0]);
+        speed.toggle_pause();
+        assert_eq!(ticks(&mut speed, 1), [1]);
+        // Frame advance while running pauses first.
+        speed.step();
+        assert!(speed.is_paused());
+        assert_eq!(ticks(&mut speed, 1), [0]);
+    }
+
+    #[test]
+    fn fast_forward_and_slow_motion() {
+        let mut speed = Speed::new(11);
+        speed.set_fast_forward_rate(3);
+        speed.set_fast_forward(true);
+        assert_eq!(ticks(&mut speed, 2), [3, 3]);
+        speed.set_fast_forward(false);
+        speed.toggle_slow_motion();
+        assert_eq!(ticks(&mut speed, 8), [0, 0, 0, 1, 0, 0, 0, 1]);
+        // Holding fast forward still works in slow motion.
+        speed.set_fast_forward(true);
+        assert_eq!(ticks(&mut speed, 1), [3]);
+        speed.set_fast_forward(false);
+        speed.toggle_slow_motion();
+        assert!(speed.is_normal());
+    }
+
+    #[test]
+    fn instructions_per_frame() {
+        let mut speed = Speed::new(11);
+        speed.faster();
+        assert_eq!(speed.tick_rate(), 12);
+        let mut speed = Speed::new(100);
+        speed.faster();
+        assert_eq!(speed.tick_rate(), 112);
+        speed.slower();
+        assert_eq!(speed.tick_rate(), 98);
+        let mut speed = Speed::new(1);
+        speed.slower();
+        assert_eq!(speed.tick_rate(), 1);
+        let mut speed = Speed::new(u16::MAX);
+        speed.faster();
+        assert_eq!(speed.tick_rate(), u16::MAX);
+    }
+
+    #[test]
+    fn status_text() {
+        let mut speed = Speed::new(11);
+        assert_eq!(speed.status(), "11");
+        speed.toggle_slow_motion();
+        assert_eq!(speed.status(), "x1/4 11");
+        speed.set_fast_forward(true);
+        assert_eq!(speed.status(), ">>x4 11");
+        speed.toggle_pause();
+        assert_eq!(speed.status(), "|| 11");
+    }
+
+    #[test]
+    fn status_is_drawn_on_a_box() {
+        let palette = Palette::classic();
+        let mut frame = vec![0xAA; 64 * 32 * 4];
+        draw_status("1", &palette, &mut frame, 64);
+        let pixel = |x: usize, y: usize| -> [u8; 4] { frame[(y * 64 + x) * 4..][..4].try_into().unwrap() };
+        // The 1's top row is .#. inside the 1 pixel border.
+        assert_eq!(pixel(0, 0), palette.background());
+        assert_eq!(pixel(1, 1), palette.background());
+        assert_eq!(pixel(2, 1), palette.foreground());
+        assert_eq!(pixel(2, 5), palette.foreground());
+        assert_eq!(pixel(4, 6), palette.background());
+        assert_eq!(pixel(5, 0), [0xAA; 4]);
+        assert_eq!(pixel(0, 7), [0xAA; 4]);
+    }
+}
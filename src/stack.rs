@@ -0,0 +1,199 @@
+// Call stack for 2NNN/00EE. Real interpreters had a fixed number of slots,
+// what happens when a ROM goes past them depends on the policy.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::Error;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StackPolicy {
+    // Overflow and underflow stop the interpreter.
+    Error,
+    // The stack pointer wraps around, overwriting the oldest entry on overflow
+    // and returning whatever is left in the top slot on underflow.
+    Wrap,
+    // Pushing to a full stack drops the return address, popping an empty stack does nothing.
+    Ignore,
+}
+
+pub struct Stack {
+    slots: Vec<u16>,
+    // None means the stack grows up to UNLIMITED_DEPTH, handy for debugging runaway recursion.
+    depth: Option<usize>,
+    policy: StackPolicy,
+    // Index of the next free slot.
+    top: usize,
+    len: usize,
+}
+
+impl Stack {
+    pub const VIP_DEPTH: usize = 12;
+    pub const SCHIP_DEPTH: usize = 16;
+    // The deepest fixed stack, `Chip8::new` turns anything bigger down.
+    pub const MAX_DEPTH: usize = 4096;
+    // Where an unlimited stack gives up and overflows after all, whatever the policy,
+    // so a ROM that calls itself forever doesn't eat all the memory there is.
+    pub const UNLIMITED_DEPTH: usize = 1 << 20;
+
+    pub fn new(depth: Option<usize>, policy: StackPolicy) -> Stack {
+        // A zero depth stack can't wrap, give it at least one slot.
+        let depth = depth.map(|depth| depth.clamp(1, Self::MAX_DEPTH));
+        Stack {
+            slots: vec![0; depth.unwrap_or(0)],
+            depth,
+            policy,
+            top: 0,
+            len: 0,
+        }
+    }
+
+    pub fn depth(&self) -> Option<usize> {
+        self.depth
+    }
+
+    pub fn policy(&self) -> StackPolicy {
+        self.policy
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Live return addresses, oldest first.
+    pub fn entries(&self) -> Vec<u16> {
+        match self.depth {
+            None => self.slots.clone(),
+            Some(depth) => (0..self.len)
+                .map(|i| self.slots[(self.top + depth - self.len + i) % depth])
+                .collect(),
+        }
+    }
+
+    // The slots as they are, with the next free one and how many are live, for save states.
+    pub(crate) fn raw(&self) -> (&[u16], usize, usize) {
+        (&self.slots, self.top, self.len)
+    }
+
+    // A stack with this one's depth and policy holding `slots`, or None if no
+    // stack like it could have got that way.
+    pub(crate) fn with_raw(&self, slots: Vec<u16>, top: usize, len: usize) -> Option<Stack> {
+        let fits = match self.depth {
+            None => top == 0 && len == slots.len() && len <= Self::UNLIMITED_DEPTH,
+            Some(depth) => slots.len() == depth && top < depth && len <= depth,
+        };
+        fits.then_some(Stack { slots, depth: self.depth, policy: self.policy, top, len })
+    }
+
+    pub fn push(&mut self, address: u16) -> Result<(), Error> {
+        let depth = match self.depth {
+            None if self.len == Self::UNLIMITED_DEPTH => return Err(Error::StackOverflow(self.len)),
+            None => {
+                self.slots.push(address);
+                self.len += 1;
+                return Ok(());
+            }
+            Some(depth) => depth,
+        };
+        if self.len == depth {
+            match self.policy {
+                StackPolicy::Error => return Err(Error::StackOverflow(depth)),
+                StackPolicy::Ignore => return Ok(()),
+                StackPolicy::Wrap => self.len -= 1,
+            }
+        }
+        self.slots[self.top] = address;
+        self.top = (self.top + 1) % depth;
+        self.len += 1;
+        Ok(())
+    }
+
+    // Returns None when an underflow is ignored.
+    pub fn pop(&mut self) -> Result<Option<u16>, Error> {
+        if self.len == 0 {
+            match (self.policy, self.depth) {
+                (StackPolicy::Error, _) => return Err(Error::StackUnderflow),
+                // Nothing to wrap around to on an unlimited stack.
+                (StackPolicy::Ignore, _) | (StackPolicy::Wrap, None) => return Ok(None),
+                (StackPolicy::Wrap, Some(depth)) => {
+                    self.top = (self.top + depth - 1) % depth;
+                    return Ok(Some(self.slots[self.top]));
+                }
+            }
+        }
+        self.len -= 1;
+        match self.depth {
+            None => Ok(self.slots.pop()),
+            Some(depth) => {
+                self.top = (self.top + depth - 1) % depth;
+                Ok(Some(self.slots[self.top]))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_depth_is_usable() {
+        let mut stack = Stack::new(Some(Stack::SCHIP_DEPTH), StackPolicy::Error);
+        for i in 0..16 {
+            stack.push(i).unwrap();
+        }
+        assert!(stack.push(16).is_err());
+        assert_eq!(stack.entries(), (0..16).collect::<Vec<_>>());
+        for i in (0..16).rev() {
+            assert_eq!(stack.pop().unwrap(), Some(i));
+        }
+        assert!(stack.pop().is_err());
+    }
+
+    #[test]
+    fn wrap_overwrites_the_oldest_entry() {
+        let mut stack = Stack::new(Some(2), StackPolicy::Wrap);
+        stack.push(1).unwrap();
+        stack.push(2).unwrap();
+        stack.push(3).unwrap();
+        assert_eq!(stack.entries(), vec![2, 3]);
+        assert_eq!(stack.pop().unwrap(), Some(3));
+        assert_eq!(stack.pop().unwrap(), Some(2));
+        // Underflow reads the stale slot below.
+        assert_eq!(stack.pop().unwrap(), Some(3));
+    }
+
+    #[test]
+    fn ignore_drops_calls_and_returns() {
+        let mut stack = Stack::new(Some(1), StackPolicy::Ignore);
+        stack.push(1).unwrap();
+        stack.push(2).unwrap();
+        assert_eq!(stack.entries(), vec![1]);
+        assert_eq!(stack.pop().unwrap(), Some(1));
+        assert_eq!(stack.pop().unwrap(), None);
+    }
+
+    #[test]
+    fn unlimited_never_overflows() {
+        let mut stack = Stack::new(None, StackPolicy::Error);
+        for i in 0..1000 {
+            stack.push(i).unwrap();
+        }
+        assert_eq!(stack.len(), 1000);
+        assert_eq!(stack.pop().unwrap(), Some(999));
+    }
+
+    #[test]
+    fn unlimited_has_a_ceiling() {
+        let mut stack = Stack::new(None, StackPolicy::Ignore);
+        for _ in 0..Stack::UNLIMITED_DEPTH {
+            stack.push(0x200).unwrap();
+        }
+        assert_eq!(stack.push(0x200), Err(Error::StackOverflow(Stack::UNLIMITED_DEPTH)));
+    }
+}
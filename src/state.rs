@@ -0,0 +1,232 @@
+// Save states: everything a running ROM can change, so loading one carries on
+// exactly where it was saved. The options aren't in there, a state only loads
+// into an interpreter with the same memory size and stack depth, and the block
+// cache starts over after loading.
+//
+// A magic number and version, then the fields in the order `save_state` writes
+// them, big endian.
+
+use alloc::vec::Vec;
+
+use crate::{Chip8, Display, Error, Key, KeyWaitState, Keypad, Rng};
+
+const MAGIC: &[u8; 4] = b"C8SS";
+const VERSION: u8 = 1;
+
+impl Chip8 {
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut state = Vec::new();
+        state.extend(MAGIC);
+        state.push(VERSION);
+        state.extend(self.pc.to_be_bytes());
+        state.extend(self.ir.to_be_bytes());
+        state.extend(self.vr);
+        state.push(self.delay_timer);
+        state.push(self.sound_timer);
+        state.extend(self.rng.state().to_be_bytes());
+        state.extend(self.instructions.to_be_bytes());
+        for keypad in [self.keypad, self.key_wait_edges] {
+            for bits in keypad.to_bits() {
+                state.extend(bits.to_be_bytes());
+            }
+        }
+        state.extend(match self.key_wait {
+            KeyWaitState::Idle => [0, 0],
+            KeyWaitState::Waiting => [1, 0],
+            KeyWaitState::Held(key) => [2, key.index() as u8],
+        });
+        state.push(self.waiting_for_vblank.into());
+
+        let (slots, top, len) = self.stack.raw();
+        for value in [slots.len(), top, len] {
+            state.extend((value as u32).to_be_bytes());
+        }
+        for slot in slots {
+            state.extend(slot.to_be_bytes());
+        }
+
+        let memory = self.memory.as_slice();
+        state.extend((memory.len() as u32).to_be_bytes());
+        state.extend(memory);
+
+        let display = &self.display;
+        state.extend([display.width(), display.height(), display.planes() as u8]);
+        for plane in 0..display.planes() {
+            for word in display.plane(plane) {
+                state.extend(word.to_be_bytes());
+            }
+        }
+        state
+    }
+
+    // Nothing changes unless the whole state loads.
+    pub fn load_state(&mut self, state: &[u8]) -> Result<(), Error> {
+        let mut reader = Reader(state);
+        if reader.bytes(4)? != MAGIC || reader.u8()? != VERSION {
+            return Err(Error::InvalidSaveState);
+        }
+        let pc = reader.u16()?;
+        let ir = reader.u16()?;
+        let vr = reader.bytes(16)?.try_into().map_err(|_| Error::InvalidSaveState)?;
+        let delay_timer = reader.u8()?;
+        let sound_timer = reader.u8()?;
+        let rng = match reader.u64()? {
+            0 => return Err(Error::InvalidSaveState),
+            rng => Rng::new(rng),
+        };
+        let instructions = reader.u64()?;
+        let mut keypads = [Keypad::new(); 2];
+        for keypad in keypads.iter_mut() {
+            *keypad = Keypad::from_bits([reader.u16()?, reader.u16()?, reader.u16()?, reader.u16()?]);
+        }
+        let key_wait = match [reader.u8()?, reader.u8()?] {
+            [0, 0] => KeyWaitState::Idle,
+            [1, 0] => KeyWaitState::Waiting,
+            [2, key] => KeyWaitState::Held(Key::new(key).ok_or(Error::InvalidSaveState)?),
+            _ => return Err(Error::InvalidSaveState),
+        };
+        let waiting_for_vblank = match reader.u8()? {
+            0 => false,
+            1 => true,
+            _ => return Err(Error::InvalidSaveState),
+        };
+
+        let slots = reader.u32()?;
+        let (top, len) = (reader.u32()?, reader.u32()?);
+        // Checked before reading so a made up length can't allocate much.
+        if slots > reader.0.len() / 2 {
+            return Err(Error::InvalidSaveState);
+        }
+        let slots = (0..slots).map(|_| reader.u16()).collect::<Result<Vec<_>, _>>()?;
+        let stack = self.stack.with_raw(slots, top, len).ok_or(Error::InvalidSaveState)?;
+
+        let size = reader.u32()?;
+        if size != self.memory.size() {
+            return Err(Error::InvalidSaveState);
+        }
+        let memory = reader.bytes(size)?;
+
+        let [width, height, planes] = [reader.u8()?, reader.u8()?, reader.u8()?];
+        // XO-CHIP's two planes are as many as anything draws.
+        if width == 0 || height == 0 || !(1..=2).contains(&planes) {
+            return Err(Error::InvalidSaveState);
+        }
+        let mut display = Display::with_size(width, height, planes);
+        display.set_sprite_edge(self.display.sprite_edge());
+        for plane in 0..display.planes() {
+            let words = (0..display.plane(plane).len()).map(|_| reader.u64()).collect::<Result<Vec<_>, _>>()?;
+            if !display.load_plane(plane, &words) {
+                return Err(Error::InvalidSaveState);
+            }
+        }
+        if !reader.0.is_empty() {
+            return Err(Error::InvalidSaveState);
+        }
+
+        self.memory.load(0, memory)?;
+        self.cache.clear();
+        self.pc = pc;
+        self.ir = ir;
+        self.vr = vr;
+        self.delay_timer = delay_timer;
+        self.sound_timer = sound_timer;
+        self.rng = rng;
+        self.instructions = instructions;
+        [self.keypad, self.key_wait_edges] = keypads;
+        self.key_wait = key_wait;
+        self.waiting_for_vblank = waiting_for_vblank;
+        self.stack = stack;
+        self.display = display;
+        self.last_tick = None;
+        Ok(())
+    }
+}
+
+// Reads a save state front to back, any read past the end is an invalid state.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if len > self.0.len() {
+            return Err(Error::InvalidSaveState);
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, Error> {
+        Ok(u16::from_be_bytes([self.u8()?, self.u8()?]))
+    }
+
+    fn u32(&mut self) -> Result<usize, Error> {
+        Ok(u32::from_be_bytes([self.u8()?, self.u8()?, self.u8()?, self.u8()?]) as usize)
+    }
+
+    fn u64(&mut self) -> Result<u64, Error> {
+        let bytes = self.bytes(8)?;
+        Ok(u64::from_be_bytes(bytes.try_into().map_err(|_| Error::InvalidSaveState)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Options, StackPolicy};
+
+    fn chip8() -> Chip8 {
+        let mut options = Options::new();
+        options.set_seed(Some(1));
+        let mut chip8 = Chip8::new(options).unwrap();
+        // Draws a counter with the font, calls a subroutine and waits for a key.
+        chip8.load_rom(vec![
+            0x60, 0x00, 0x22, 0x10, 0xF1, 0x0A, 0x70, 0x01, 0x12, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0xC2, 0xFF, 0xF0, 0x29, 0xD3, 0x45, 0x00, 0xEE,
+        ]).unwrap();
+        chip8
+    }
+
+    #[test]
+    fn loading_a_state_carries_on_from_it() {
+        let mut chip8 = chip8();
+        chip8.run_cpu_cycle_alternate(3).unwrap();
+        chip8.set_key(Key::K7, true);
+        let state = chip8.save_state();
+
+        let mut copy = self::chip8();
+        copy.load_state(&state).unwrap();
+        assert_eq!(copy.save_state(), state);
+        for _ in 0..5 {
+            chip8.run_cpu_cycle_alternate(7).unwrap();
+            copy.run_cpu_cycle_alternate(7).unwrap();
+        }
+        chip8.set_key(Key::K7, false);
+        copy.set_key(Key::K7, false);
+        chip8.run_cpu_cycle_alternate(7).unwrap();
+        copy.run_cpu_cycle_alternate(7).unwrap();
+        assert_eq!(copy.save_state(), chip8.save_state());
+        assert!(copy.display().lit_pixels().eq(chip8.display().lit_pixels()));
+    }
+
+    #[test]
+    fn bad_states_change_nothing() {
+        let mut chip8 = chip8();
+        chip8.run_cpu_cycle_alternate(3).unwrap();
+        let state = chip8.save_state();
+        let mut fresh = self::chip8();
+        let before = fresh.save_state();
+        for bad in [&state[..state.len() - 1], &[&state[..], &[0]].concat(), b"C8SS\x02", &[]] {
+            assert_eq!(fresh.load_state(bad), Err(Error::InvalidSaveState));
+        }
+        // A different stack depth.
+        let mut options = Options::new();
+        options.set_stack_depth(Some(12));
+        options.set_stack_policy(StackPolicy::Wrap);
+        assert_eq!(Chip8::new(options).unwrap().load_state(&state), Err(Error::InvalidSaveState));
+        assert_eq!(fresh.save_state(), before);
+    }
+}
@@ -0,0 +1,198 @@
+// Runs the interpreter in the terminal, two display rows per line of half block characters.
+
+use chip_egg::{AudioSink, Chip8, Clock, Display, InputSource, Key, KeyEvent, Keymap, VideoSink};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::style::{Color, ResetColor, SetBackgroundColor, SetForegroundColor};
+use crossterm::{cursor, queue, terminal};
+
+#[cfg(feature = "audio")]
+use crate::audio::Beeper;
+use crate::config::Config;
+use crate::TICK_RATE;
+
+use std::collections::VecDeque;
+use std::io::{self, BufWriter, Stdout, Write};
+use std::time::{Duration, Instant};
+
+const FRAME: Duration = Duration::from_micros(16666);
+// Most terminals only report presses, so a key counts as held for this many
+// frames after its last press or auto-repeat.
+const HOLD_FRAMES: u8 = 8;
+
+struct TuiFrontend {
+    out: BufWriter<Stdout>,
+    next_frame: Instant,
+    keys: VecDeque<KeyEvent>,
+    keymap: Keymap<char>,
+    // Frames left before each keypad key is let go.
+    held: [u8; 16],
+    quit: bool,
+    // Size of the last frame drawn, everything is redrawn when it changes.
+    size: Option<(usize, usize)>,
+    #[cfg(feature = "audio")]
+    beeper: Option<Beeper>,
+}
+
+impl TuiFrontend {
+    fn press(&mut self, key: Key) {
+        if self.held[key.index()] == 0 {
+            self.keys.push_back(KeyEvent::new(key, true));
+        }
+        self.held[key.index()] = HOLD_FRAMES;
+    }
+
+    fn release(&mut self, key: Key) {
+        if self.held[key.index()] > 0 {
+            self.keys.push_back(KeyEvent::new(key, false));
+        }
+        self.held[key.index()] = 0;
+    }
+
+    fn handle_events(&mut self) -> io::Result<()> {
+        while event::poll(Duration::ZERO)? {
+            let key = match event::read()? {
+                Event::Key(key) => key,
+                _ => continue,
+            };
+            if key.code == KeyCode::Esc {
+                self.quit = true;
+                continue;
+            }
+            let keypad = match key.code {
+                KeyCode::Char(c) => self.keymap.get(&c.to_ascii_lowercase()),
+                _ => None,
+            };
+            if let Some(keypad) = keypad {
+                match key.kind {
+                    KeyEventKind::Release => self.release(keypad),
+                    _ => self.press(keypad),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, display: &Display) -> io::Result<()> {
+        let width = display.width() as usize;
+        let height = display.height() as usize;
+        let full = self.size != Some((width, height));
+        if full {
+            queue!(self.out, terminal::Clear(terminal::ClearType::All))?;
+            self.size = Some((width, height));
+        }
+        for line in 0..height.div_ceil(2) {
+            let top = line * 2;
+            let bottom = top + 1;
+            let dirty = display.is_row_dirty(top) || (bottom < height && display.is_row_dirty(bottom));
+            if !full && !dirty {
+                continue;
+            }
+            queue!(self.out, cursor::MoveTo(0, line as u16))?;
+            let mut text = String::with_capacity(width * 3);
+            for x in 0..width {
+                let upper = display.pixel(x, top) != 0;
+                let lower = bottom < height && display.pixel(x, bottom) != 0;
+                text.push(match (upper, lower) {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => ' ',
+                });
+            }
+            write!(self.out, "{}", text)?;
+        }
+        self.out.flush()
+    }
+}
+
+impl VideoSink for TuiFrontend {
+    fn present(&mut self, display: &Display) {
+        if let Err(e) = self.draw(display) {
+            log::error!("Couldn't draw to the terminal: {}", e);
+            self.quit = true;
+        }
+    }
+}
+
+impl AudioSink for TuiFrontend {
+    #[cfg(feature = "audio")]
+    fn set_tone(&mut self, playing: bool) {
+        if let Some(beeper) = &mut self.beeper {
+            beeper.set_tone(playing);
+        }
+    }
+
+    #[cfg(not(feature = "audio"))]
+    fn set_tone(&mut self, _playing: bool) {}
+}
+
+impl InputSource for TuiFrontend {
+    fn poll_key(&mut self) -> Option<KeyEvent> {
+        self.keys.pop_front()
+    }
+}
+
+impl Clock for TuiFrontend {
+    fn wait_frame(&mut self) -> bool {
+        let now = Instant::now();
+        if self.next_frame > now {
+            std::thread::sleep(self.next_frame - now);
+        }
+        // Don't try to catch up after a stall, just carry on from here.
+        self.next_frame = Instant::now().max(self.next_frame) + FRAME;
+
+        for key in Key::ALL {
+            let held = &mut self.held[key.index()];
+            if *held > 0 {
+                *held -= 1;
+                if *held == 0 {
+                    self.keys.push_back(KeyEvent::new(key, false));
+                }
+            }
+        }
+        if let Err(e) = self.handle_events() {
+            log::error!("Couldn't read terminal input: {}", e);
+            self.quit = true;
+        }
+        !self.quit
+    }
+}
+
+fn to_color(rgba: [u8; 4]) -> Color {
+    Color::Rgb { r: rgba[0], g: rgba[1], b: rgba[2] }
+}
+
+// Runs until Esc is pressed. The terminal is put back the way it was even if the interpreter fails.
+pub fn run(mut chip8: Chip8, config: &Config) -> Result<(), String> {
+    let palette = config.palette();
+    let mut out = BufWriter::new(io::stdout());
+    terminal::enable_raw_mode().map_err(|e| e.to_string())?;
+    queue!(
+        out,
+        terminal::EnterAlternateScreen,
+        cursor::Hide,
+        SetForegroundColor(to_color(palette.foreground())),
+        SetBackgroundColor(to_color(palette.background())),
+    )
+    .map_err(|e| e.to_string())?;
+
+    let mut frontend = TuiFrontend {
+        out,
+        next_frame: Instant::now(),
+        keys: VecDeque::new(),
+        // Same layout as the window.
+        keymap: Keymap::qwerty(Some),
+        held: [0; 16],
+        quit: false,
+        size: None,
+        #[cfg(feature = "audio")]
+        beeper: Beeper::new().ok(),
+    };
+    let result = chip8.run(&mut frontend, TICK_RATE);
+
+    let mut out = frontend.out;
+    let _ = queue!(out, ResetColor, cursor::Show, terminal::LeaveAlternateScreen);
+    let _ = out.flush();
+    let _ = terminal::disable_raw_mode();
+    result.map_err(String::from)
+}
@@ -1,32 +1,361 @@
 use winit::{
-    dpi::LogicalSize,
+    dpi::{LogicalSize, PhysicalPosition},
     event::*,
     event_loop::{ControlFlow, EventLoop},
     window::WindowBuilder,
 };
 use pixels::{Error, Pixels, SurfaceTexture};
 
-use crate::{Chip8, Options, TIMER_RATE, TICK_RATE};
+use chip_egg::{apply_crt_effects, compose_frame, save_screenshot, timestamped_path, AudioSink, CrtEffects, CrtRenderer, Display, HeldKeys, InputSource, KeyEvent, Keymap, Keypad, KeypadOverlay, Palette, Phosphor, Recorder, Speed, VideoSink};
+#[cfg(feature = "gamepad")]
+use chip_egg::{Gamepads, Key};
+
+#[cfg(feature = "audio")]
+use crate::audio::Beeper;
+use crate::config::Config;
+#[cfg(feature = "gamepad")]
+use crate::controllers::GilrsBackend;
+use crate::{Chip8, TIMER_RATE, TICK_RATE};
 // use crate::CPU_CLOCK;
 
+use std::fs::File;
+use std::io::{BufWriter, Write};
 use std::time::{Duration, Instant};
 
-pub struct Chip8Window {
+// How long the speed indicator stays up after +/-.
+const STATUS_TIME: Duration = Duration::from_secs(2);
+// How much bigger the pixels buffer is than the frame when the CRT effects are drawn on the CPU.
+const CPU_CRT_SCALE: usize = 4;
+
+// The CRT effects, drawn by the shader or on the CPU when its pipeline wouldn't build.
+enum Crt {
+    Gpu(CrtRenderer),
+    // `frame` is the composed frame before it's scaled up into the pixels buffer.
+    Cpu { effects: CrtEffects, frame: Vec<u8> },
+}
+
+// Whatever is holding a key down. Gamepads count their own pads and buttons, so
+// they come through as one holder per key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Holder {
+    Keyboard(VirtualKeyCode),
+    #[cfg(feature = "gamepad")]
+    Gamepads(Key),
+    // A touch id, or None for the mouse.
+    Pointer(Option<u64>),
+}
+
+impl Crt {
+    // Expects the pixels buffer to be `width` x `height`, and scales it up for the CPU.
+    fn new(pixels: &mut Pixels, width: usize, height: usize, effects: CrtEffects) -> Crt {
+        match CrtRenderer::new(pixels, width as u32, height as u32, effects) {
+            Ok(renderer) => Crt::Gpu(renderer),
+            Err(e) => {
+                log::warn!("No CRT shader, drawing the effects on the CPU: {}", e);
+                pixels.resize_buffer((width * CPU_CRT_SCALE) as u32, (height * CPU_CRT_SCALE) as u32);
+                Crt::Cpu { effects, frame: vec![0; width * height * 4] }
+            }
+        }
+    }
+
+    fn effects(&self) -> CrtEffects {
+        match self {
+            Crt::Gpu(renderer) => renderer.effects(),
+            Crt::Cpu { effects, .. } => *effects,
+        }
+    }
+
+    // Pixels buffer pixels per frame pixel.
+    fn scale(&self) -> usize {
+        match self {
+            Crt::Gpu(_) => 1,
+            Crt::Cpu { .. } => CPU_CRT_SCALE,
+        }
+    }
+}
+
+// The winit/pixels side of the window: everything the core draws to and reads keys from.
+pub struct WindowFrontend {
     pixels: Pixels,
+    palette: Palette,
+    phosphor: Phosphor,
+    crt: Crt,
+    recorder: Option<Recorder<Box<dyn Write>>>,
+    // Keys held from winit and the gamepads, handed to the core at the start of the next frame.
+    keys: HeldKeys<Holder>,
+    overlay: KeypadOverlay,
+    // The keypad as of the last frame, for the overlay.
+    keypad: Keypad,
+    // The CHIP-8 screen on its own, before the overlay is drawn around it.
+    screen: Vec<u8>,
+    // The speed indicator in the corner, when there's something to show.
+    status: Option<String>,
+    #[cfg(feature = "audio")]
+    beeper: Option<Beeper>,
+}
+
+impl WindowFrontend {
+    pub fn render(&mut self) {
+        let status = self.status.as_deref();
+        match &mut self.crt {
+            Crt::Gpu(crt) => {
+                let frame = self.pixels.get_frame_mut();
+                compose_frame(&self.phosphor, &self.overlay, &self.keypad, &self.palette, status, &mut self.screen, frame);
+                let crt = &*crt;
+                self.pixels.render_with(|encoder, render_target, context| {
+                    let clip_rect = context.scaling_renderer.clip_rect();
+                    if crt.effects().is_empty() {
+                        context.scaling_renderer.render(encoder, render_target);
+                    } else {
+                        crt.render(encoder, render_target, clip_rect);
+                    }
+                    Ok(())
+                }).expect("Error rendering window");
+            }
+            Crt::Cpu { effects, frame } => {
+                compose_frame(&self.phosphor, &self.overlay, &self.keypad, &self.palette, status, &mut self.screen, frame);
+                let (width, height) = self.overlay.frame_size();
+                let scaled = apply_crt_effects(*effects, frame, width, height, CPU_CRT_SCALE);
+                self.pixels.get_frame_mut().copy_from_slice(&scaled);
+                self.pixels.render().expect("Error rendering window");
+            }
+        }
+    }
+}
+
+impl VideoSink for WindowFrontend {
+    fn present(&mut self, display: &Display) {
+        self.phosphor.update(display);
+        if let Some(recorder) = &mut self.recorder {
+            if let Err(e) = recorder.record(display) {
+                log::error!("Recording stopped: {}", e);
+                self.recorder = None;
+            }
+        }
+        if display.redraw() || self.phosphor.fading() {
+            self.render();
+        }
+    }
+}
+
+impl AudioSink for WindowFrontend {
+    #[cfg(feature = "audio")]
+    fn set_tone(&mut self, playing: bool) {
+        if let Some(beeper) = &mut self.beeper {
+            beeper.set_tone(playing);
+        }
+    }
+
+    #[cfg(not(feature = "audio"))]
+    fn set_tone(&mut self, _playing: bool) {}
+}
+
+impl InputSource for WindowFrontend {
+    fn poll_key(&mut self) -> Option<KeyEvent> {
+        self.keys.poll_key()
+    }
+}
+
+pub struct Chip8Window {
     chip8: Chip8,
+    frontend: WindowFrontend,
+    // Set once the interpreter hits an error, we stop running it but keep the window open.
+    halted: bool,
+    config: Config,
+    keymap: Keymap<VirtualKeyCode>,
+    speed: Speed,
+    // When +/- was last pressed, to show the new speed for a bit.
+    speed_changed: Option<Instant>,
+    cursor: PhysicalPosition<f64>,
+    // None when gilrs couldn't start, the keyboard still works.
+    #[cfg(feature = "gamepad")]
+    gamepads: Option<Gamepads<GilrsBackend>>,
 }
 
 impl Chip8Window {
-    pub fn new(pixels: Pixels, chip8: Chip8) -> Chip8Window {
-        Chip8Window {
+    // The ROM's file name picks its controller profile.
+    #[cfg_attr(not(feature = "gamepad"), allow(unused_variables))]
+    pub fn new(mut pixels: Pixels, chip8: Chip8, config: Config, recorder: Option<Recorder<Box<dyn Write>>>, rom: &str) -> Chip8Window {
+        let display = chip8.display();
+        let (width, height) = (display.width() as usize, display.height() as usize);
+        let overlay = KeypadOverlay::new(config.keypad_overlay(), width, height);
+        let (frame_width, frame_height) = overlay.frame_size();
+        let crt = Crt::new(&mut pixels, frame_width, frame_height, config.crt_effects());
+        let frontend = WindowFrontend {
             pixels,
+            palette: config.palette(),
+            phosphor: Phosphor::new(config.persistence()),
+            crt,
+            recorder,
+            keys: HeldKeys::new(),
+            overlay,
+            keypad: Keypad::new(),
+            screen: vec![0; width * height * 4],
+            status: None,
+            #[cfg(feature = "audio")]
+            beeper: Beeper::new()
+                .map_err(|e| log::warn!("No sound: {}", e))
+                .ok(),
+        };
+        let mut speed = Speed::new(TICK_RATE);
+        speed.set_fast_forward_rate(config.fast_forward());
+        #[cfg(feature = "gamepad")]
+        let gamepads = GilrsBackend::new()
+            .map(|backend| Gamepads::new(backend, config.gamepad_keymap(rom)))
+            .map_err(|e| log::warn!("No gamepads: {}", e))
+            .ok();
+        Chip8Window {
             chip8,
+            frontend,
+            halted: false,
+            config,
+            keymap: Keymap::qwerty(keycode),
+            speed,
+            speed_changed: None,
+            cursor: PhysicalPosition::new(0.0, 0.0),
+            #[cfg(feature = "gamepad")]
+            gamepads,
+        }
+    }
+
+    pub fn next_palette(&mut self) -> bool {
+        self.frontend.palette = self.frontend.palette.next();
+        self.config.set("palette", self.frontend.palette.name());
+        self.config.save();
+        self.render();
+        true
+    }
+
+    pub fn screenshot(&mut self) -> bool {
+        match save_screenshot(".", self.chip8.display(), &self.frontend.palette, self.config.scale()) {
+            Ok(path) => log::info!("Saved screenshot to {}", path.display()),
+            Err(e) => log::error!("Couldn't save screenshot: {}", e),
+        }
+        true
+    }
+
+    pub fn toggle_recording(&mut self) -> bool {
+        if self.frontend.recorder.is_some() {
+            self.stop_recording();
+            return true;
+        }
+        let path = timestamped_path(".", "gif");
+        let file: Box<dyn Write> = match File::create(&path) {
+            Ok(file) => Box::new(BufWriter::new(file)),
+            Err(e) => {
+                log::error!("Couldn't create {}: {}", path.display(), e);
+                return true;
+            }
+        };
+        match Recorder::gif(file, &self.frontend.palette, self.config.scale(), self.chip8.display()) {
+            Ok(recorder) => {
+                log::info!("Recording to {}", path.display());
+                self.frontend.recorder = Some(recorder);
+            }
+            Err(e) => log::error!("Couldn't start recording: {}", e),
+        }
+        true
+    }
+
+    pub fn stop_recording(&mut self) {
+        if let Some(recorder) = self.frontend.recorder.take() {
+            match recorder.finish() {
+                Ok(_) => log::info!("Recording stopped"),
+                Err(e) => log::error!("Couldn't finish recording: {}", e),
+            }
+        }
+    }
+
+    pub fn next_crt_effects(&mut self) -> bool {
+        let frontend = &mut self.frontend;
+        let effects = frontend.crt.effects().next();
+        match &mut frontend.crt {
+            Crt::Gpu(renderer) => renderer.set_effects(&frontend.pixels.context().queue, effects),
+            Crt::Cpu { effects: current, .. } => *current = effects,
+        }
+        self.config.set("crt", &effects.to_string());
+        self.config.save();
+        self.render();
+        true
+    }
+
+    pub fn next_overlay(&mut self) -> bool {
+        let frontend = &mut self.frontend;
+        let mode = frontend.overlay.mode().next();
+        frontend.overlay.set_mode(mode);
+        // The keys under the mouse and touches move or go away, so let go of them.
+        frontend.keys.release_all(|holder| matches!(holder, Holder::Pointer(_)));
+        // The side panel makes the frame wider, and the CRT shader samples the old texture.
+        let (width, height) = frontend.overlay.frame_size();
+        frontend.pixels.resize_buffer(width as u32, height as u32);
+        let effects = frontend.crt.effects();
+        frontend.crt = Crt::new(&mut frontend.pixels, width, height, effects);
+        self.config.set("keypad", &mode.to_string());
+        self.config.save();
+        self.render();
+        true
+    }
+
+    pub fn toggle_pause(&mut self) -> bool {
+        self.speed.toggle_pause();
+        true
+    }
+
+    pub fn frame_advance(&mut self) -> bool {
+        self.speed.step();
+        true
+    }
+
+    pub fn toggle_slow_motion(&mut self) -> bool {
+        self.speed.toggle_slow_motion();
+        true
+    }
+
+    pub fn change_speed(&mut self, faster: bool) -> bool {
+        if faster {
+            self.speed.faster();
+        } else {
+            self.speed.slower();
+        }
+        self.speed_changed = Some(Instant::now());
+        log::info!("{} instructions per frame", self.speed.tick_rate());
+        true
+    }
+
+    // Shown while paused, fast forwarding or in slow motion, and for a bit after +/-.
+    fn status(&self) -> Option<String> {
+        let changed = self.speed_changed.is_some_and(|at| at.elapsed() < STATUS_TIME);
+        (!self.speed.is_normal() || changed).then(|| self.speed.status())
+    }
+
+    // A click or touch on the overlay presses the key under it until it's let go.
+    // `pointer` is the touch id, or None for the mouse. The key stays down while the
+    // keyboard or a gamepad holds it too.
+    fn press_overlay(&mut self, pointer: Option<u64>, position: PhysicalPosition<f64>, pressed: bool) -> bool {
+        if !pressed {
+            return self.frontend.keys.release(&Holder::Pointer(pointer)).is_some();
+        }
+        let key = self
+            .frontend
+            .pixels
+            .window_pos_to_pixel((position.x as f32, position.y as f32))
+            .ok()
+            .and_then(|(x, y)| {
+                let scale = self.frontend.crt.scale();
+                self.frontend.overlay.key_at(x / scale, y / scale)
+            });
+        match key {
+            Some(key) => {
+                self.frontend.keys.press(Holder::Pointer(pointer), key);
+                true
+            }
+            None => false,
         }
     }
 
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
         if new_size.width > 0 && new_size.height > 0 {
-            self.pixels.resize_surface(new_size.width, new_size.height);
+            self.frontend.pixels.resize_surface(new_size.width, new_size.height);
         }
     }
 
@@ -41,94 +370,122 @@ impl Chip8Window {
                     },
                 ..
             } => {
-                match state {
-                    ElementState::Pressed => {
-                        match keycode {
-                            VirtualKeyCode::Key1 => self.chip8.set_key(0x1, true),
-                            VirtualKeyCode::Key2 => self.chip8.set_key(0x2, true),
-                            VirtualKeyCode::Key3 => self.chip8.set_key(0x3, true),
-                            VirtualKeyCode::Key4 => self.chip8.set_key(0xC, true),
-                            VirtualKeyCode::Q => self.chip8.set_key(0x4, true),
-                            VirtualKeyCode::W => self.chip8.set_key(0x5, true),
-                            VirtualKeyCode::E => self.chip8.set_key(0x6, true),
-                            VirtualKeyCode::R => self.chip8.set_key(0xD, true),
-                            VirtualKeyCode::A => self.chip8.set_key(0x7, true),
-                            VirtualKeyCode::S => self.chip8.set_key(0x8, true),
-                            VirtualKeyCode::D => self.chip8.set_key(0x9, true),
-                            VirtualKeyCode::F => self.chip8.set_key(0xE, true),
-                            VirtualKeyCode::Z => self.chip8.set_key(0xA, true),
-                            VirtualKeyCode::X => self.chip8.set_key(0x0, true),
-                            VirtualKeyCode::C => self.chip8.set_key(0xB, true),
-                            VirtualKeyCode::V => self.chip8.set_key(0xF, true),
-                            _ => false
-                        }
-                    }
-                    ElementState::Released => {
-                        match keycode {
-                            VirtualKeyCode::Key1 => self.chip8.set_key(0x1, false),
-                            VirtualKeyCode::Key2 => self.chip8.set_key(0x2, false),
-                            VirtualKeyCode::Key3 => self.chip8.set_key(0x3, false),
-                            VirtualKeyCode::Key4 => self.chip8.set_key(0xC, false),
-                            VirtualKeyCode::Q => self.chip8.set_key(0x4, false),
-                            VirtualKeyCode::W => self.chip8.set_key(0x5, false),
-                            VirtualKeyCode::E => self.chip8.set_key(0x6, false),
-                            VirtualKeyCode::R => self.chip8.set_key(0xD, false),
-                            VirtualKeyCode::A => self.chip8.set_key(0x7, false),
-                            VirtualKeyCode::S => self.chip8.set_key(0x8, false),
-                            VirtualKeyCode::D => self.chip8.set_key(0x9, false),
-                            VirtualKeyCode::F => self.chip8.set_key(0xE, false),
-                            VirtualKeyCode::Z => self.chip8.set_key(0xA, false),
-                            VirtualKeyCode::X => self.chip8.set_key(0x0, false),
-                            VirtualKeyCode::C => self.chip8.set_key(0xB, false),
-                            VirtualKeyCode::V => self.chip8.set_key(0xF, false),
-                            _ => false
-                        }
+                let pressed = *state == ElementState::Pressed;
+                if let Some(key) = self.keymap.get(keycode) {
+                    let holder = Holder::Keyboard(*keycode);
+                    if pressed {
+                        self.frontend.keys.press(holder, key);
+                    } else {
+                        self.frontend.keys.release(&holder);
                     }
+                    return true;
+                }
+                // Fast forward lasts as long as it's held.
+                if *keycode == VirtualKeyCode::Tab {
+                    self.speed.set_fast_forward(pressed);
+                    return true;
+                }
+                if !pressed {
+                    return false;
                 }
+                match keycode {
+                    VirtualKeyCode::Space => self.toggle_pause(),
+                    VirtualKeyCode::N => self.frame_advance(),
+                    VirtualKeyCode::M => self.toggle_slow_motion(),
+                    VirtualKeyCode::Equals | VirtualKeyCode::Plus | VirtualKeyCode::NumpadAdd => self.change_speed(true),
+                    VirtualKeyCode::Minus | VirtualKeyCode::NumpadSubtract => self.change_speed(false),
+                    VirtualKeyCode::P => self.next_palette(),
+                    VirtualKeyCode::G => self.next_crt_effects(),
+                    VirtualKeyCode::F1 => self.next_overlay(),
+                    VirtualKeyCode::F10 => self.toggle_recording(),
+                    VirtualKeyCode::F12 => self.screenshot(),
+                    _ => false
+                }
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor = *position;
+                false
+            }
+            WindowEvent::MouseInput { state, button: MouseButton::Left, .. } => {
+                self.press_overlay(None, self.cursor, *state == ElementState::Pressed)
             }
+            WindowEvent::Touch(Touch { phase, location, id, .. }) => match phase {
+                TouchPhase::Started => self.press_overlay(Some(*id), *location, true),
+                TouchPhase::Ended | TouchPhase::Cancelled => self.press_overlay(Some(*id), *location, false),
+                TouchPhase::Moved => false,
+            },
             _ => false
         }
     }
 
     pub fn update(&mut self) {
-        // self.chip8.run_cpu_cycle().expect("Error");
-        self.chip8.run_cpu_cycle_alternate(TICK_RATE).expect("Error"); // Runs much faster...
-        if self.chip8.display().redraw() {
-            self.render();
-            // chip8_window.pixels.render().expect("Error rendering window");
-            self.chip8.display_mut().reset_redraw();
+        self.frontend.status = self.status();
+        if self.halted {
+            return;
+        }
+        #[cfg(feature = "gamepad")]
+        if let Some(gamepads) = &mut self.gamepads {
+            while let Some(event) = gamepads.poll_key() {
+                let holder = Holder::Gamepads(event.key);
+                if event.pressed {
+                    self.frontend.keys.press(holder, event.key);
+                } else {
+                    self.frontend.keys.release(&holder);
+                }
+            }
+        }
+        let frames = self.speed.frames();
+        if frames == 0 && self.speed.is_paused() {
+            self.frontend.set_tone(false);
+        }
+        for _ in 0..frames {
+            if let Err(e) = self.chip8.run_frame(&mut self.frontend, self.speed.tick_rate()) {
+                log::error!("Interpreter halted: {}", e);
+                self.halted = true;
+                break;
+            }
         }
+        self.frontend.keypad = *self.chip8.keypad();
     }
 
     pub fn render(&mut self) {
-        let frame = self.pixels.get_frame_mut();
-        for (i, pixel) in frame.chunks_exact_mut(4).enumerate() {
-            let display = self.chip8.display();
-            let x = i % display.width() as usize;
-            let y = i / display.width() as usize;
-
-            let rgba = if display.display()[x][y] == 0 {
-                [0x00, 0x00, 0x00, 0xff]
-            } else {
-                [0xff, 0xff, 0xff, 0xff]
-            };
-
-            pixel.copy_from_slice(&rgba);
-        }
-        self.pixels.render().expect("Error rendering window");
+        self.frontend.render();
     }
 }
 
-pub async fn run(rom: Vec<u8>) -> Result<(), Error> {
+// The keys on a QWERTY keyboard, for `Keymap::qwerty`.
+fn keycode(c: char) -> Option<VirtualKeyCode> {
+    let keycode = match c {
+        '1' => VirtualKeyCode::Key1,
+        '2' => VirtualKeyCode::Key2,
+        '3' => VirtualKeyCode::Key3,
+        '4' => VirtualKeyCode::Key4,
+        'q' => VirtualKeyCode::Q,
+        'w' => VirtualKeyCode::W,
+        'e' => VirtualKeyCode::E,
+        'r' => VirtualKeyCode::R,
+        'a' => VirtualKeyCode::A,
+        's' => VirtualKeyCode::S,
+        'd' => VirtualKeyCode::D,
+        'f' => VirtualKeyCode::F,
+        'z' => VirtualKeyCode::Z,
+        'x' => VirtualKeyCode::X,
+        'c' => VirtualKeyCode::C,
+        'v' => VirtualKeyCode::V,
+        _ => return None,
+    };
+    Some(keycode)
+}
+
+pub async fn run(chip8: Chip8, config: Config, recorder: Option<Recorder<Box<dyn Write>>>, rom: &str) -> Result<(), Error> {
     env_logger::init();
     let event_loop = EventLoop::new();
-    
-    let options = Options::new();
-    let chip8 = Chip8::new(options);
+
     let display = chip8.display();
 
-    let width = display.width();
-    let height = display.height();
+    // The keypad overlay's side panel makes the frame wider than the CHIP-8 screen.
+    let overlay = KeypadOverlay::new(config.keypad_overlay(), display.width() as usize, display.height() as usize);
+    let (width, height) = overlay.frame_size();
     
     let window = {
         let size = LogicalSize::new(width as f64, height as f64);
@@ -147,8 +504,7 @@ pub async fn run(rom: Vec<u8>) -> Result<(), Error> {
         Pixels::new(width as u32, height as u32, surface_texture)?
     };
 
-    let mut chip8_window = Chip8Window::new(pixels, chip8);
-    chip8_window.chip8.load_rom(rom);
+    let mut chip8_window = Chip8Window::new(pixels, chip8, config, recorder, rom);
     // let mut last_cpu_tick = Instant::now();
     let mut last_timer_tick = Instant::now();
 
@@ -157,7 +513,7 @@ pub async fn run(rom: Vec<u8>) -> Result<(), Error> {
             Event::WindowEvent {
                 ref event,
                 window_id,
-            } if window_id == window.id() => if !chip8_window.input(event) {
+            } if window_id == window.id() && !chip8_window.input(event) => {
                 match event {
                     WindowEvent::CloseRequested
                     | WindowEvent::KeyboardInput {
@@ -177,10 +533,9 @@ pub async fn run(rom: Vec<u8>) -> Result<(), Error> {
                     _ => {}
                 }
             }
-            Event::RedrawRequested(window_id) if window_id == window.id() => {
-                chip8_window.update();
-                chip8_window.render();
-            }
+            // Only the 60Hz tick below runs the game, redraws can come any number of times a tick.
+            Event::RedrawRequested(window_id) if window_id == window.id() => chip8_window.render(),
+            Event::LoopDestroyed => chip8_window.stop_recording(),
             Event::MainEventsCleared => {
                 // if last_cpu_tick.elapsed() >= Duration::from_micros(CPU_CLOCK) { // old timing method has perf issues...
                 //     last_cpu_tick = Instant::now();
@@ -0,0 +1,178 @@
+// Runs the test ROMs in tests/roms headlessly and compares the final display with
+// the golden images in tests/golden. Every engine has to produce the same picture.
+//
+// After an intended change to what a ROM shows, regenerate the goldens with
+//     UPDATE_GOLDENS=1 cargo test --test conformance
+// and check the new images by eye before committing them.
+
+#![cfg(feature = "std")]
+
+use chip_egg::{save_png, Chip8, Display, Engine, Key, Options, Palette, Quirks};
+
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+struct Case {
+    rom: &'static str,
+    // Defaults to the ROM name, for ROMs run under more than one profile.
+    golden: Option<&'static str>,
+    quirks: fn() -> Quirks,
+    frames: u32,
+    // (frame, key, pressed), applied before running that frame.
+    keys: &'static [(u32, Key, bool)],
+}
+
+impl Case {
+    const fn new(rom: &'static str) -> Case {
+        Case { rom, golden: None, quirks: Quirks::new, frames: 60, keys: &[] }
+    }
+
+    fn golden(&self) -> PathBuf {
+        let name = self.golden.unwrap_or(self.rom);
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(format!("{}.png", name))
+    }
+}
+
+const TICK_RATE: u16 = 700 / 60;
+
+fn engines() -> Vec<Engine> {
+    ["interpreter", "cached", "jit"].into_iter().filter_map(Engine::from_name).collect()
+}
+
+fn run(case: &Case, engine: Engine) -> Chip8 {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/roms").join(format!("{}.ch8", case.rom));
+    let rom = fs::read(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+    let mut options = Options::new();
+    options.set_quirks((case.quirks)());
+    options.set_engine(engine);
+    options.set_seed(Some(1));
+    let mut chip8 = Chip8::new(options).unwrap();
+    chip8.load_rom(rom).unwrap();
+    for frame in 0..case.frames {
+        for &(_, key, pressed) in case.keys.iter().filter(|(at, _, _)| *at == frame) {
+            chip8.set_key(key, pressed);
+        }
+        chip8.run_cpu_cycle_alternate(TICK_RATE).unwrap();
+    }
+    chip8
+}
+
+// The golden as rows of lit pixels, going by the classic palette's background colour.
+fn load_golden(path: &Path) -> Vec<Vec<bool>> {
+    let file = File::open(path).unwrap_or_else(|e| panic!("{}: {} (UPDATE_GOLDENS=1 makes it)", path.display(), e));
+    let mut reader = png::Decoder::new(file).read_info().unwrap();
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer).unwrap();
+    let background = Palette::classic().background();
+    let rows = buffer[..info.buffer_size()].chunks(info.line_size);
+    rows.map(|row| row.chunks(4).map(|pixel| pixel != background).collect()).collect()
+}
+
+fn pixels(display: &Display) -> Vec<Vec<bool>> {
+    (0..display.height() as usize)
+        .map(|y| (0..display.width() as usize).map(|x| display.pixel(x, y) != 0).collect())
+        .collect()
+}
+
+// Expected and actual side by side, with the differing rows marked.
+fn diff(expected: &[Vec<bool>], actual: &[Vec<bool>]) -> String {
+    let line = |row: Option<&Vec<bool>>| -> String {
+        row.map_or_else(String::new, |row| row.iter().map(|&lit| if lit { '#' } else { '.' }).collect())
+    };
+    let mut out = String::from("expected | actual\n");
+    for y in 0..expected.len().max(actual.len()) {
+        let (a, b) = (expected.get(y), actual.get(y));
+        let mark = if a == b { ' ' } else { '!' };
+        out += &format!("{}{} | {}\n", mark, line(a), line(b));
+    }
+    out
+}
+
+fn check(case: &Case) {
+    let golden = case.golden();
+    for engine in engines() {
+        let chip8 = run(case, engine);
+        let display = chip8.display();
+        if std::env::var_os("UPDATE_GOLDENS").is_some() && engine == Engine::Interpreter {
+            save_png(&golden, display, &Palette::classic(), 1).unwrap();
+        }
+        let expected = load_golden(&golden);
+        let actual = pixels(display);
+        if expected != actual {
+            let failed = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(golden.file_name().unwrap());
+            save_png(&failed, display, &Palette::classic(), 1).unwrap();
+            panic!(
+                "{} on {:?} doesn't match {}, saved what it drew to {}\n{}",
+                case.rom,
+                engine,
+                golden.display(),
+                failed.display(),
+                diff(&expected, &actual)
+            );
+        }
+    }
+}
+
+#[test]
+fn opcodes() {
+    check(&Case::new("opcodes"));
+}
+
+#[test]
+fn flags() {
+    check(&Case::new("flags"));
+}
+
+#[test]
+fn quirks_vip() {
+    check(&Case { golden: Some("quirks-vip"), quirks: Quirks::chip8, ..Case::new("quirks") });
+}
+
+#[test]
+fn quirks_xo_chip() {
+    check(&Case { golden: Some("quirks-xo-chip"), quirks: Quirks::xo_chip, ..Case::new("quirks") });
+}
+
+#[test]
+fn keypad() {
+    // FX0A waits for 5 to be let go, then A is held long enough for the EX9E/EXA1 checks.
+    let keys = &[(5, Key::K5, true), (8, Key::K5, false), (12, Key::KA, true), (20, Key::KA, false)];
+    check(&Case { keys, ..Case::new("keypad") });
+}
+
+#[test]
+fn bcd() {
+    check(&Case::new("bcd"));
+}
+
+#[test]
+fn font() {
+    check(&Case::new("font"));
+}
+
+// The community ROMs. They aren't checked in yet, see tests/roms/README.md for where
+// they come from and their licences. Once one is added with its golden, drop its ignore.
+
+#[test]
+#[ignore = "needs tests/roms/ibm-logo.ch8, see tests/roms/README.md"]
+fn ibm_logo() {
+    check(&Case::new("ibm-logo"));
+}
+
+#[test]
+#[ignore = "needs tests/roms/bc_test.ch8, see tests/roms/README.md"]
+fn bc_test() {
+    check(&Case { frames: 120, ..Case::new("bc_test") });
+}
+
+#[test]
+#[ignore = "needs tests/roms/timendus-corax+.ch8, see tests/roms/README.md"]
+fn timendus_corax() {
+    check(&Case { frames: 120, ..Case::new("timendus-corax+") });
+}
+
+#[test]
+#[ignore = "needs tests/roms/timendus-flags.ch8, see tests/roms/README.md"]
+fn timendus_flags() {
+    check(&Case { frames: 120, ..Case::new("timendus-flags") });
+}
@@ -0,0 +1,118 @@
+// Validates the WGSL shaders on the CPU so broken shaders get caught without a GPU,
+// and checks the CRT shader against its CPU copy when there is an adapter.
+
+use naga::valid::{Capabilities, ValidationFlags, Validator};
+
+fn validate(path: &str) {
+    let source = std::fs::read_to_string(path).unwrap();
+    let module = match naga::front::wgsl::parse_str(&source) {
+        Ok(module) => module,
+        Err(e) => panic!("{}", e.emit_to_string(&source)),
+    };
+    Validator::new(ValidationFlags::all(), Capabilities::empty())
+        .validate(&module)
+        .unwrap_or_else(|e| panic!("{}: {:?}", path, e));
+}
+
+#[test]
+fn crt_shader_is_valid() {
+    validate("shaders/crt.wgsl");
+}
+
+// Renders `frame` through the CRT shader at `scale`, or None without an adapter.
+#[cfg(feature = "window")]
+fn render_crt(effects: chip_egg::CrtEffects, frame: &[u8], width: u32, height: u32, scale: u32) -> Option<Vec<u8>> {
+    use chip_egg::{upload_frame, CrtRenderer};
+
+    let instance = wgpu::Instance::new(wgpu::Backends::all());
+    let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))?;
+    let descriptor = wgpu::DeviceDescriptor { label: None, features: wgpu::Features::empty(), limits: adapter.limits() };
+    let (device, queue) = pollster::block_on(adapter.request_device(&descriptor, None)).ok()?;
+    // Not sRGB, so the shader's maths happens on the same values `apply` sees.
+    let format = wgpu::TextureFormat::Rgba8Unorm;
+    let texture = |width: u32, height: u32, usage: wgpu::TextureUsages| {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage,
+        })
+    };
+    let (out_width, out_height) = (width * scale, height * scale);
+    let source = texture(width, height, wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST);
+    let target = texture(out_width, out_height, wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC);
+    // Rows of a texture copy have to line up on 256 bytes.
+    assert_eq!(out_width * 4 % 256, 0);
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: (out_width * out_height * 4) as u64,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let crt = CrtRenderer::with_device(&device, &source, format, width, height, effects).unwrap();
+    upload_frame(&queue, &source, frame, width, height);
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    let view = target.create_view(&wgpu::TextureViewDescriptor::default());
+    crt.render(&mut encoder, &view, (0, 0, out_width, out_height));
+    encoder.copy_texture_to_buffer(
+        target.as_image_copy(),
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: std::num::NonZeroU32::new(out_width * 4),
+                rows_per_image: std::num::NonZeroU32::new(out_height),
+            },
+        },
+        wgpu::Extent3d { width: out_width, height: out_height, depth_or_array_layers: 1 },
+    );
+    queue.submit(Some(encoder.finish()));
+    let slice = buffer.slice(..);
+    slice.map_async(wgpu::MapMode::Read, |result| result.unwrap());
+    device.poll(wgpu::Maintain::Wait);
+    let pixels = slice.get_mapped_range().to_vec();
+    Some(pixels)
+}
+
+#[cfg(feature = "window")]
+#[test]
+fn crt_shader_matches_apply() {
+    use chip_egg::{apply_crt_effects, CrtEffects};
+
+    const WIDTH: usize = 16;
+    const HEIGHT: usize = 8;
+    const SCALE: usize = 4;
+    // Something with edges and a few colours for the bloom and grid to work on.
+    let mut frame = vec![0; WIDTH * HEIGHT * 4];
+    for (i, pixel) in frame.chunks_mut(4).enumerate() {
+        let (x, y) = (i % WIDTH, i / WIDTH);
+        if (x + y) % 3 != 0 {
+            pixel.copy_from_slice(&[(x * 16) as u8, (y * 32) as u8, 0xC0, 0xFF]);
+        }
+    }
+
+    let mut effects = CrtEffects::NONE;
+    loop {
+        let Some(gpu) = render_crt(effects, &frame, WIDTH as u32, HEIGHT as u32, SCALE as u32) else {
+            eprintln!("No GPU adapter, skipping the shader comparison.");
+            return;
+        };
+        let cpu = apply_crt_effects(effects, &frame, WIDTH, HEIGHT, SCALE);
+        // Rounding can be a step off, and a pixel right on a curvature edge can
+        // land either side of it.
+        let different = gpu
+            .chunks(4)
+            .zip(cpu.chunks(4))
+            .filter(|(gpu, cpu)| gpu.iter().zip(cpu.iter()).any(|(a, b)| a.abs_diff(*b) > 2))
+            .count();
+        assert!(different <= WIDTH * HEIGHT * SCALE * SCALE / 100, "{}: {} pixels differ", effects, different);
+        effects = effects.next();
+        if effects.is_empty() {
+            break;
+        }
+    }
+}